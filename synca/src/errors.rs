@@ -0,0 +1,65 @@
+use proc_macro2::TokenStream;
+
+/// Errors collected while parsing and folding modules
+///
+/// `Fold` can't return `Result`, so errors are accumulated here
+/// and emitted as `compile_error!` after the expansion.
+#[derive(Debug, Default)]
+pub struct Errors(Vec<syn::Error>);
+
+impl Errors {
+  pub fn push(&mut self, error: syn::Error) {
+    for e in error.into_iter() {
+      let is_duplicate = self.0
+        .iter()
+        .any(|x| x.to_string() == e.to_string() && format!("{:?}", x.span()) == format!("{:?}", e.span()));
+
+      if !is_duplicate {
+        self.0.push(e);
+      }
+    }
+  }
+
+  pub fn extend(&mut self, other: &Errors) {
+    for e in other.0.iter() {
+      self.push(e.clone());
+    }
+  }
+
+  pub fn to_compile_error(&self) -> TokenStream {
+    self.0.iter().map(|x| x.to_compile_error()).collect()
+  }
+}
+
+impl PartialEq for Errors {
+  fn eq(&self, other: &Self) -> bool {
+    self.0.len() == other.0.len()
+      && self.0.iter().zip(other.0.iter()).all(|(a, b)| a.to_string() == b.to_string())
+  }
+}
+
+/// Combines results, keeping all errors
+pub fn combine(errors: &mut Option<syn::Error>, error: syn::Error) {
+  match errors {
+    Some(x) => x.combine(error),
+    None => *errors = Some(error),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use proc_macro2::Span;
+
+  use super::Errors;
+
+  #[test]
+  fn push() {
+    let mut errors = Errors::default();
+    errors.push(syn::Error::new(Span::call_site(), "first"));
+    errors.push(syn::Error::new(Span::call_site(), "first"));
+    errors.push(syn::Error::new(Span::call_site(), "second"));
+
+    assert_eq!(errors.0.len(), 2);
+    assert!(errors.to_compile_error().to_string().contains("second"));
+  }
+}
@@ -1,76 +1,187 @@
-use syn::{parse_quote, Attribute, Ident};
+use proc_macro2::Span;
+use syn::{parse::ParseStream, parse_quote, punctuated::Punctuated, Attribute, Meta, Token};
 
-#[derive(Debug, PartialEq)]
+use crate::{fold::{FnBody, ScopedRules}, CfgPredicate};
+
+#[derive(Debug)]
 pub enum SyncAAttribute {
-  Other(Attribute),
-  Cfg(Ident),
-  Doc(String),
-  Ignore,
+  Other(Box<Attribute>),
+  Cfg(CfgPredicate),
+  CfgAttr(CfgPredicate, Vec<Attribute>),
+  Doc(String, Span),
+  Scope(ScopedRules),
+  Body(Vec<FnBody>),
+  Ignore(Option<CfgPredicate>),
+  KeepAsync,
+  NoReplace,
+}
+
+impl PartialEq for SyncAAttribute {
+  fn eq(&self, other: &Self) -> bool {
+    match (self, other) {
+      (Self::Other(a), Self::Other(b)) => a == b,
+      (Self::Cfg(a), Self::Cfg(b)) => a == b,
+      (Self::CfgAttr(a, x), Self::CfgAttr(b, y)) => a == b && x == y,
+      (Self::Doc(a, _), Self::Doc(b, _)) => a == b,
+      (Self::Body(a), Self::Body(b)) => a == b,
+      (Self::Scope(a), Self::Scope(b)) => a.is_rename == b.is_rename
+        && a.cfg == b.cfg
+        && a.tokens.to_string() == b.tokens.to_string(),
+      (Self::Ignore(a), Self::Ignore(b)) => a == b,
+      (Self::KeepAsync, Self::KeepAsync) => true,
+      (Self::NoReplace, Self::NoReplace) => true,
+      _ => false,
+    }
+  }
 }
 
-impl From<Attribute> for SyncAAttribute {
-  fn from(value: Attribute) -> Self {
+impl TryFrom<Attribute> for SyncAAttribute {
+  type Error = syn::Error;
+
+  fn try_from(value: Attribute) -> syn::Result<Self> {
     if value == parse_quote!(#[synca::ignore]) {
-      return SyncAAttribute::Ignore;
+      return Ok(SyncAAttribute::Ignore(None));
+    }
+
+    if value.path() == &parse_quote!(synca::ignore) {
+      return Ok(SyncAAttribute::Ignore(Some(value.parse_args()?)));
+    }
+
+    if value == parse_quote!(#[synca::keep_async]) {
+      return Ok(SyncAAttribute::KeepAsync);
+    }
+
+    if value == parse_quote!(#[synca::no_replace]) {
+      return Ok(SyncAAttribute::NoReplace);
     }
 
     if value.path() == &parse_quote!(synca::cfg) {
-      let i: Ident = value.parse_args().unwrap();
-      return SyncAAttribute::Cfg(i.clone());
+      return Ok(SyncAAttribute::Cfg(value.parse_args()?));
+    }
+
+    for (path, is_rename) in [(parse_quote!(synca::replace), false), (parse_quote!(synca::rename), true)] {
+      if value.path() == &path {
+        return value
+          .parse_args_with(|input: ParseStream| ScopedRules::parse_with(is_rename, input))
+          .map(SyncAAttribute::Scope);
+      }
+    }
+
+    if value.path() == &parse_quote!(synca::body) {
+      return value.parse_args_with(FnBody::parse_list).map(SyncAAttribute::Body);
+    }
+
+    if value.path() == &parse_quote!(synca::cfg_attr) {
+      return value.parse_args_with(|input: ParseStream| {
+        let predicate = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let attrs = Punctuated::<Meta, Token![,]>::parse_terminated(input)?
+          .into_iter()
+          .map(|meta| Attribute { meta, ..value.clone() })
+          .collect();
+
+        Ok(SyncAAttribute::CfgAttr(predicate, attrs))
+      });
     }
 
     let name_value = match &value.meta {
       syn::Meta::NameValue(x) => x,
-      _ => return Self::Other(value)
+      _ => return Ok(Self::Other(Box::new(value)))
     };
 
     if !name_value.path.is_ident("doc") {
-      return Self::Other(value)
+      return Ok(Self::Other(Box::new(value)))
     }
 
     let lit = match &name_value.value {
       syn::Expr::Lit(x) => x,
-      _ => return Self::Other(value)
+      _ => return Ok(Self::Other(Box::new(value)))
     };
 
     match &lit.lit {
-      syn::Lit::Str(x) => Self::Doc(x.value()),
-      _ => Self::Other(value)
+      syn::Lit::Str(x) => Ok(Self::Doc(x.value(), x.span())),
+      _ => Ok(Self::Other(Box::new(value)))
     }
   }
 }
 
 #[cfg(test)]
-mod from {
+mod try_from {
+  use proc_macro2::Span;
+  use quote::quote;
   use syn::{parse_quote, Attribute};
 
-  use crate::SyncAAttribute;
+  use crate::{fold::ScopedRules, SyncAAttribute};
 
   #[test]
   fn other() {
     let attr: Attribute = parse_quote!(#[test]);
 
-    assert_eq!(SyncAAttribute::from(attr.clone()), SyncAAttribute::Other(attr));
+    assert_eq!(SyncAAttribute::try_from(attr.clone()).unwrap(), SyncAAttribute::Other(Box::new(attr)));
   }
 
   #[test]
   fn cfg() {
     let attr: Attribute = parse_quote!(#[synca::cfg(tokio)]);
 
-    assert_eq!(SyncAAttribute::from(attr), SyncAAttribute::Cfg(parse_quote!(tokio)));
+    assert_eq!(SyncAAttribute::try_from(attr).unwrap(), SyncAAttribute::Cfg(parse_quote!(tokio)));
+  }
+
+  #[test]
+  fn cfg_attr() {
+    let attr: Attribute = parse_quote!(#[synca::cfg_attr(not(sync), must_use, instrument(skip(self)))]);
+
+    assert_eq!(
+      SyncAAttribute::try_from(attr).unwrap(), 
+      SyncAAttribute::CfgAttr(
+        parse_quote!(not(sync)), 
+        vec![parse_quote!(#[must_use]), parse_quote!(#[instrument(skip(self))])]
+      )
+    );
+  }
+
+  #[test]
+  fn scope() {
+    let attr: Attribute = parse_quote!(#[synca::rename(any(sync, smol): a => b)]);
+
+    assert_eq!(
+      SyncAAttribute::try_from(attr).unwrap(), 
+      SyncAAttribute::Scope(ScopedRules { 
+        is_rename: true, 
+        cfg: Some(parse_quote!(any(sync, smol))), 
+        tokens: quote!(a => b) 
+      })
+    );
   }
 
   #[test]
   fn doc() {
     let attr: Attribute = parse_quote!(#[doc = "my text"]);
 
-    assert_eq!(SyncAAttribute::from(attr.clone()), SyncAAttribute::Doc("my text".into()));
+    assert_eq!(
+      SyncAAttribute::try_from(attr.clone()).unwrap(), 
+      SyncAAttribute::Doc("my text".into(), Span::call_site())
+    );
   }
 
   #[test]
   fn ignore() {
     let attr: Attribute = parse_quote!(#[synca::ignore]);
 
-    assert_eq!(SyncAAttribute::from(attr.clone()), SyncAAttribute::Ignore);
+    assert_eq!(SyncAAttribute::try_from(attr.clone()).unwrap(), SyncAAttribute::Ignore(None));
+
+    let attr: Attribute = parse_quote!(#[synca::ignore(not(sync))]);
+
+    assert_eq!(
+      SyncAAttribute::try_from(attr.clone()).unwrap(), 
+      SyncAAttribute::Ignore(Some(parse_quote!(not(sync))))
+    );
+  }
+
+  #[test]
+  fn cfg_error() {
+    let attr: Attribute = parse_quote!(#[synca::cfg(tokio, sync)]);
+
+    assert!(SyncAAttribute::try_from(attr).is_err());
   }
 }
\ No newline at end of file
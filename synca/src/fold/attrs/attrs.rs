@@ -1,60 +1,82 @@
-use syn::{parse_quote, Attribute};
+use syn::Attribute;
 
-use crate::{SyncAFold, SyncAAttribute};
+use crate::{errors::combine, SyncAFold, SyncAAttribute};
 
 pub struct SyncAFoldAttributes {
+  pub module_name: String,
   pub is_async: bool,
   pub ignored: bool,
   pub new_attrs: Vec<Attribute>,
 }
 
 impl SyncAFoldAttributes {
-  pub fn new(fold: &SyncAFold, attrs: &Vec<Attribute>) -> Self {
+  pub fn new(fold: &SyncAFold, attrs: &[Attribute]) -> syn::Result<Self> {
     let mut result = SyncAFoldAttributes { 
+      module_name: fold.module_name.clone(),
       is_async: fold.is_async,
       ignored: false, 
       new_attrs: vec![]
     };
     let mut docs = vec![];
-
-    for attr in attrs.into_iter() {
-      let synca_attr: SyncAAttribute = attr.clone().into();
-      match &synca_attr {
+    let mut errors = None;
+
+    for attr in attrs.iter() {
+      let synca_attr = match SyncAAttribute::try_from(attr.clone()) {
+        Ok(x) => x,
+        Err(e) => {
+          combine(&mut errors, e);
+          continue;
+        }
+      };
+
+      match synca_attr {
         SyncAAttribute::Other(x) => {
-          result.new_attrs.push(
-            match fold.attributes.get(x) {
-              Some(n) => if fold.is_async { x.clone() } else { n.clone() },
-              None => x.clone(),
-            }
-          );
-        },
-        SyncAAttribute::Cfg(x) => {
-          if x != &fold.module_name {
-            let cfg = &fold.cfg;
-            result.new_attrs.push(parse_quote!(#[cfg(all(#cfg, not(#cfg)))]));
+          match fold.replace_attribute(&x) {
+            Some(n) if fold.replaces() => result.new_attrs.extend(n),
+            _ => result.new_attrs.push(*x),
           }
         },
-        SyncAAttribute::Doc(s) => 
-          for str in s.split("\n") { 
-            docs.push(str.to_string());
+        // Filtered nodes are removed by their containers, see `fold::cfg`
+        SyncAAttribute::Cfg(x) => if !x.matches(fold) {
+          combine(&mut errors, syn::Error::new_spanned(
+            attr,
+            "SyncA can't remove this node: #[synca::cfg] is supported on items, statements, fields, \
+              variants, match arms, arguments, list elements and generic params"
+          ));
+        },
+        SyncAAttribute::CfgAttr(x, attrs) => if x.matches(fold) {
+          result.new_attrs.extend(attrs);
+        },
+        SyncAAttribute::Doc(s, span) => 
+          for str in s.split('\n') { 
+            docs.push((str.to_string(), span));
           },
-        SyncAAttribute::Ignore => result.ignored = true,
+        SyncAAttribute::Ignore(x) => if x.as_ref().is_none_or(|x| x.matches(fold)) {
+          result.ignored = true;
+        },
+        // Applied by `SyncAFold::scoped` and `SyncAFold::fn_body`
+        SyncAAttribute::Scope(_) 
+          | SyncAAttribute::Body(_) 
+          | SyncAAttribute::KeepAsync 
+          | SyncAAttribute::NoReplace => { },
       }
     }
 
     match result.docs_attribute(docs) {
-      Some(x) => result.new_attrs.push(x),
-      None => { }
+      Ok(Some(x)) => result.new_attrs.push(x),
+      Ok(None) => { },
+      Err(e) => combine(&mut errors, e),
     }
 
-    result
+    match errors {
+      Some(e) => Err(e),
+      None => Ok(result),
+    }
   }
 }
 
 #[cfg(test)]
 mod from {
-  use std::collections::HashMap;
-
   use quote::ToTokens;
   use syn::{parse_quote, Attribute};
 
@@ -74,19 +96,17 @@ mod from {
     let attrs = |is_async| {
       SyncAFoldAttributes::new(
         &SyncAFold { 
-          module_name: "tokio".into(),
-          is_async: is_async, 
-          types: HashMap::new(), 
-          attributes: HashMap::from([
-            (parse_quote!(#[tokio::test]), parse_quote!(#[test]))
-          ]),
-          cfg: parse_quote!(feature = "tokio")
+          is_async, 
+          attributes: vec![
+            (parse_quote!(#[tokio::test]), vec![parse_quote!(#[test])])
+          ],
+          ..SyncAFold::new("tokio", parse_quote!(feature = "tokio"))
         },
-        &vec![
+        &[
           parse_quote!(#[custom]),
           parse_quote!(#[tokio::test]),
         ]
-      )
+      ).unwrap()
     };
 
     assert_eq!(
@@ -121,20 +141,60 @@ mod from {
     let attrs = |is_ignored| {
       SyncAFoldAttributes::new(
         &SyncAFold { 
-          module_name: "tokio".into(),
           is_async: true, 
-          types: HashMap::new(), 
-          attributes: HashMap::from([
-            (parse_quote!(#[tokio::test]), parse_quote!(#[test]))
-          ]),
-          cfg: parse_quote!(feature = "tokio")
+          attributes: vec![
+            (parse_quote!(#[tokio::test]), vec![parse_quote!(#[test])])
+          ],
+          ..SyncAFold::new("tokio", parse_quote!(feature = "tokio"))
         },
         if is_ignored { &attrs_ignored } else { &attrs_simple }
-      )
+      ).unwrap()
+    };
+
+    assert!(attrs(true).ignored);
+    assert!(!attrs(false).ignored);
+
+    let attrs = |module_name, is_async| {
+      SyncAFoldAttributes::new(
+        &SyncAFold { is_async, ..SyncAFold::new(module_name, parse_quote!(feature = "sync")) },
+        &[parse_quote!(#[synca::ignore(sync)]), parse_quote!(#[custom])]
+      ).unwrap()
+    };
+
+    assert!(attrs("sync", false).ignored);
+    assert!(!attrs("tokio", true).ignored);
+    assert_eq!(attrs("tokio", true).new_attrs, vec![parse_quote!(#[custom])]);
+  }
+
+  #[test]
+  fn cfg_attr() {
+    let attrs = |module_name, is_async| {
+      SyncAFoldAttributes::new(
+        &SyncAFold { is_async, ..SyncAFold::new(module_name, parse_quote!(feature = "tokio")) },
+        &[
+          parse_quote!(#[custom]),
+          parse_quote!(#[synca::cfg_attr(sync, derive(Clone))]),
+          parse_quote!(#[synca::cfg_attr(tokio, must_use, instrument)]),
+        ]
+      ).unwrap()
+        .new_attrs
+        .iter()
+        .map(|x| x.to_token_stream().to_string())
+        .collect::<Vec<_>>()
     };
+    let expected = |attrs: Vec<Attribute>| attrs
+      .iter()
+      .map(|x| x.to_token_stream().to_string())
+      .collect::<Vec<_>>();
 
-    assert_eq!(attrs(true).ignored, true);
-    assert_eq!(attrs(false).ignored, false);
+    assert_eq!(
+      attrs("tokio", true), 
+      expected(vec![parse_quote!(#[custom]), parse_quote!(#[must_use]), parse_quote!(#[instrument])])
+    );
+    assert_eq!(
+      attrs("blocking", false), 
+      expected(vec![parse_quote!(#[custom]), parse_quote!(#[derive(Clone)])])
+    );
   }
 
   #[test]
@@ -151,20 +211,18 @@ mod from {
     let attrs = |is_async| {
       SyncAFoldAttributes::new(
         &SyncAFold { 
-          module_name: "sync".into(),
           is_async, 
-          types: HashMap::new(), 
-          attributes: HashMap::from([
-            (parse_quote!(#[tokio::test]), parse_quote!(#[test]))
-          ]),
-          cfg: parse_quote!(feature = "sync")
+          attributes: vec![
+            (parse_quote!(#[tokio::test]), vec![parse_quote!(#[test])])
+          ],
+          ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
         },
-        &vec![ 
+        &[ 
           parse_quote!(#[custom]), 
           parse_quote!(#[doc = " # Header"]),
           parse_quote!(#[doc = " [synca::match]async|sync[/synca::match]"]),
         ]
-      )
+      ).unwrap()
     };
 
     assert_eq!(
@@ -0,0 +1,86 @@
+use syn::{parse::{Parse, ParseStream}, punctuated::Punctuated, Ident, Token};
+
+use crate::SyncAFold;
+
+/// Argument of `#[synca::cfg(..)]`
+///
+/// `async` and `sync` select modules by mode, other names select modules by name.
+#[derive(Debug, PartialEq)]
+pub enum CfgPredicate {
+  Name(Ident),
+  Async,
+  Any(Vec<CfgPredicate>),
+  All(Vec<CfgPredicate>),
+  Not(Box<CfgPredicate>),
+}
+
+impl CfgPredicate {
+  pub fn matches(&self, fold: &SyncAFold) -> bool {
+    self.matches_module(&fold.module_name, fold.is_async)
+  }
+
+  pub fn matches_module(&self, module_name: &str, is_async: bool) -> bool {
+    match self {
+      Self::Name(x) => *x == module_name || (x == "sync" && !is_async),
+      Self::Async => is_async,
+      Self::Any(x) => x.iter().any(|x| x.matches_module(module_name, is_async)),
+      Self::All(x) => x.iter().all(|x| x.matches_module(module_name, is_async)),
+      Self::Not(x) => !x.matches_module(module_name, is_async),
+    }
+  }
+}
+
+impl Parse for CfgPredicate {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    if input.parse::<Option<Token![async]>>()?.is_some() {
+      return Ok(Self::Async);
+    }
+
+    let ident: Ident = input.parse()?;
+    if !input.peek(syn::token::Paren) {
+      return Ok(Self::Name(ident));
+    }
+
+    let content;
+    syn::parenthesized!(content in input);
+    let mut list: Vec<_> = Punctuated::<CfgPredicate, Token![,]>::parse_terminated(&content)?
+      .into_iter()
+      .collect();
+
+    match ident.to_string().as_str() {
+      "any" => Ok(Self::Any(list)),
+      "all" => Ok(Self::All(list)),
+      "not" if list.len() == 1 => Ok(Self::Not(Box::new(list.remove(0)))),
+      _ => Err(syn::Error::new(
+        ident.span(),
+        "SyncA expected cfg predicate: module name, async, sync, any(..), all(..) or not(..)"
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use syn::parse_quote;
+
+  use crate::{CfgPredicate, SyncAFold};
+
+  #[test]
+  fn matches() {
+    let tokio = SyncAFold::new("tokio", parse_quote!(feature = "tokio"));
+    let smol = SyncAFold::new("smol", parse_quote!(feature = "smol"));
+    let blocking = SyncAFold { is_async: false, ..SyncAFold::new("blocking", parse_quote!(feature = "blocking")) };
+
+    let predicate: CfgPredicate = parse_quote!(any(tokio, smol));
+    assert!(predicate.matches(&tokio) && predicate.matches(&smol) && !predicate.matches(&blocking));
+
+    let predicate: CfgPredicate = parse_quote!(not(sync));
+    assert!(predicate.matches(&tokio) && !predicate.matches(&blocking));
+
+    let predicate: CfgPredicate = parse_quote!(all(async, not(smol)));
+    assert!(predicate.matches(&tokio) && !predicate.matches(&smol) && !predicate.matches(&blocking));
+
+    assert!(syn::parse_str::<CfgPredicate>("not(tokio, smol)").is_err());
+    assert!(syn::parse_str::<CfgPredicate>("one(tokio)").is_err());
+  }
+}
@@ -1,31 +1,40 @@
+use proc_macro2::Span;
 use syn::{parse_quote, Attribute};
 
-use crate::SyncAFoldAttributes;
+use crate::{CfgPredicate, SyncAFoldAttributes};
 
 impl SyncAFoldAttributes {
-  pub fn docs_attribute(&self, docs: Vec<String>) -> Option<Attribute> {
+  pub fn docs_attribute(&self, docs: Vec<(String, Span)>) -> syn::Result<Option<Attribute>> {
     let mut is_empty = true;
     let mut result = vec![];
     let mut state = DocState::None;
+    let mut state_span = Span::call_site();
 
     macro_rules! process_doc_state {
-      ($trimmed: expr, $token: expr) => {
+      ($trimmed: expr, $span: expr, $token: expr) => {
         if $trimmed == $token.to_string() {
           if state != DocState::None {
-            panic!(
-              r#"async::docs unhandled open {} - "{}" not closed"#, 
-              $token.to_string(),
-              state.to_string()
-            );
+            return Err(syn::Error::new(
+              $span,
+              format!(
+                r#"synca::docs unhandled open {} - "{}" not closed"#, 
+                $token,
+                state
+              )
+            ));
           }
   
           state = $token;
+          state_span = $span;
           continue;
         }
 
         if $trimmed == $token.end_token() {
           if state != $token {
-            panic!("async::docs {} unhandled close", state.end_token());
+            return Err(syn::Error::new(
+              $span,
+              format!("synca::docs {} unhandled close", $token.end_token())
+            ));
           }
   
           state = DocState::None;
@@ -34,17 +43,45 @@ impl SyncAFoldAttributes {
       };
     }
 
-    for s in docs.iter() {
+    for (s, span) in docs.iter() {
       let trimmed = s.trim();
 
-      process_doc_state!(trimmed, DocState::Async);
-      process_doc_state!(trimmed, DocState::Sync);
+      process_doc_state!(trimmed, *span, DocState::Async);
+      process_doc_state!(trimmed, *span, DocState::Sync);
 
-      if (self.is_async && state == DocState::Sync) || (!self.is_async && state == DocState::Async) {
+      // [synca::module(tokio)] .. [/synca::module]
+      if let Some(x) = trimmed.strip_prefix("[synca::module(").and_then(|x| x.strip_suffix(")]")) {
+        if state != DocState::None {
+          return Err(syn::Error::new(
+            *span,
+            format!(r#"synca::docs unhandled open [synca::module] - "{}" not closed"#, state)
+          ));
+        }
+
+        state = DocState::Module(self.doc_predicate(x, *span)?);
+        state_span = *span;
+        continue;
+      }
+
+      if trimmed == DocState::Module(true).end_token() {
+        if !matches!(state, DocState::Module(_)) {
+          return Err(syn::Error::new(
+            *span,
+            format!("synca::docs {} unhandled close", DocState::Module(true).end_token())
+          ));
+        }
+
+        state = DocState::None;
         continue;
       }
 
-      let processed = self.process_str(s);
+      if (self.is_async && state == DocState::Sync) 
+        || (!self.is_async && state == DocState::Async) 
+        || state == DocState::Module(false) {
+        continue;
+      }
+
+      let processed = self.process_str(s, *span)?;
       if processed.contains(|x| x != ' ') {
         is_empty = false;
       }
@@ -52,15 +89,63 @@ impl SyncAFoldAttributes {
       result.push(processed);
     }
 
+    if state != DocState::None {
+      return Err(syn::Error::new(state_span, format!(r#"synca::docs "{}" not closed"#, state)));
+    }
+
     if is_empty { 
-      return None; 
+      return Ok(None); 
     }
 
     let processed = result.join("\n");
-    Some(parse_quote!(#[doc = #processed]))
+    Ok(Some(parse_quote!(#[doc = #processed])))
+  }
+
+  /// Predicate of `[synca::module(..)]` and arms of `[synca::match(..)]` matches the module
+  fn doc_predicate(&self, s: &str, span: Span) -> syn::Result<bool> {
+    match syn::parse_str::<CfgPredicate>(s) {
+      Ok(x) => Ok(x.matches_module(&self.module_name, self.is_async)),
+      Err(e) => Err(syn::Error::new(span, format!(r#"synca::docs "{}": {}"#, s, e))),
+    }
+  }
+
+  /// `[synca::match(tokio=Tokio|async_std=Async-std|_=Blocking)]` => `Tokio` in the tokio module
+  fn process_match_arms(&self, s: &str, span: Span) -> syn::Result<String> {
+    const START: &str = "[synca::match(";
+    const END: &str = ")]";
+
+    let mut result = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find(START) {
+      result.push_str(&rest[..start]);
+      rest = &rest[start + START.len()..];
+
+      let end = match rest.find(END) {
+        Some(x) => x,
+        None => return Err(syn::Error::new(span, format!(r#"[synca::match(..)]: not closed "{}""#, s))),
+      };
+
+      for arm in rest[..end].split(SYNCA_MATCH_DELIMITER) {
+        let (key, text) = arm.split_once('=').ok_or_else(|| syn::Error::new(
+          span, 
+          format!(r#"[synca::match(..)]: expected module=text, found "{}""#, arm)
+        ))?;
+
+        if key.trim() == "_" || self.doc_predicate(key, span)? {
+          result.push_str(text);
+          break;
+        }
+      }
+
+      rest = &rest[end + END.len()..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
   }
 
-  fn process_str(&self, s: &str) -> String {
+  fn process_str(&self, s: &str, span: Span) -> syn::Result<String> {
+    let s = &self.process_match_arms(s, span)?;
     let mut state = DocStringState::Start(0);
     let mut result = vec![];
 
@@ -112,8 +197,8 @@ impl SyncAFoldAttributes {
     }
     
     match state {
-      DocStringState::Start(_) => result.iter().collect(),
-      _ => panic!(r#"[synca::match]: not closed "{}""#, s),
+      DocStringState::Start(_) => Ok(result.iter().collect()),
+      _ => Err(syn::Error::new(span, format!(r#"[synca::match]: not closed "{}""#, s))),
     }
   }
 }
@@ -122,7 +207,9 @@ impl SyncAFoldAttributes {
 enum DocState {
   None,
   Async,
-  Sync
+  Sync,
+  /// Block of `[synca::module(..)]`, true if it matches the module
+  Module(bool),
 }
 
 impl DocState {
@@ -131,16 +218,18 @@ impl DocState {
       DocState::None => "None".into(),
       DocState::Async => "[/synca::async]".into(),
       DocState::Sync => "[/synca::sync]".into(),
+      DocState::Module(_) => "[/synca::module]".into(),
     }
   }
 }
 
-impl ToString for DocState {
-  fn to_string(&self) -> String {
+impl std::fmt::Display for DocState {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      DocState::None => "None".into(),
-      DocState::Async => "[synca::async]".into(),
-      DocState::Sync => "[synca::sync]".into(),
+      DocState::None => write!(f, "None"),
+      DocState::Async => write!(f, "[synca::async]"),
+      DocState::Sync => write!(f, "[synca::sync]"),
+      DocState::Module(_) => write!(f, "[synca::module]"),
     }
   }
 }
@@ -157,6 +246,7 @@ const SYNCA_MATCH_END: [ char; 15 ] = [ '[', '/', 's', 'y', 'n', 'c', 'a', ':',
 
 #[cfg(test)]
 mod docs_attribute {
+  use proc_macro2::Span;
   use syn::{parse_quote, Attribute};
 
   use crate::SyncAFoldAttributes;
@@ -210,18 +300,50 @@ mod docs_attribute {
     );
   }
 
+  #[test]
+  fn not_closed() {
+    assert!(try_process(" [synca::async]\n Async", true).is_err());
+    assert!(try_process(" [synca::async]\n [synca::sync]", true).is_err());
+    assert!(try_process(" Text\n [/synca::sync]", false).is_err());
+  }
+
+  #[test]
+  fn synca_module() {
+    let s = " # Header\n [synca::module(tokio)]\n Tokio\n [/synca::module]\n [synca::module(not(tokio))]\n Other\n [/synca::module]";
+
+    assert_eq!(
+      process(s),
+      (
+        Some(parse_quote!(#[doc = " # Header\n Tokio"])),
+        Some(parse_quote!(#[doc = " # Header\n Other"]))
+      )
+    );
+    assert!(try_process(" [synca::module(tokio)]\n Tokio", true).is_err());
+    assert!(try_process(" [synca::module(tokio, sync)]\n [/synca::module]", true).is_err());
+    assert!(try_process(" [synca::async]\n [/synca::module]", true).is_err());
+  }
+
   fn process(s: &str) -> (Option<Attribute>, Option<Attribute>) {
-    (
-      SyncAFoldAttributes { is_async: true, ignored: true, new_attrs: vec![] }
-        .docs_attribute(s.split("\n").map(|x| x.into()).collect()),
-      SyncAFoldAttributes { is_async: false, ignored: true, new_attrs: vec![] }
-        .docs_attribute(s.split("\n").map(|x| x.into()).collect())
-    )
+    (try_process(s, true).unwrap(), try_process(s, false).unwrap())
+  }
+
+  fn module_name(is_async: bool) -> String {
+    match is_async {
+      true => "tokio".into(),
+      false => "sync".into(),
+    }
+  }
+
+  fn try_process(s: &str, is_async: bool) -> syn::Result<Option<Attribute>> {
+    SyncAFoldAttributes { module_name: module_name(is_async), is_async, ignored: true, new_attrs: vec![] }
+      .docs_attribute(s.split('\n').map(|x| (x.into(), Span::call_site())).collect())
   }
 }
 
 #[cfg(test)]
 mod process_str {
+  use proc_macro2::Span;
+
   use crate::SyncAFoldAttributes;
 
   #[test]
@@ -258,17 +380,38 @@ mod process_str {
   }
 
   #[test]
-  #[should_panic]
+  fn match_modules() {
+    let process = |module_name: &str, is_async| SyncAFoldAttributes { 
+      module_name: module_name.into(), 
+      is_async, 
+      ignored: true, 
+      new_attrs: vec![] 
+    }
+      .process_str("Runtime: [synca::match(tokio=Tokio|async_std=async-std|_=none)], [synca::match]a|s[/synca::match]", Span::call_site())
+      .unwrap();
+
+    assert_eq!(process("tokio", true), "Runtime: Tokio, a");
+    assert_eq!(process("async_std", true), "Runtime: async-std, a");
+    assert_eq!(process("sync", false), "Runtime: none, s");
+  }
+
+  #[test]
   fn match_not_closed() {
-    process("Numbers: [synca::match];");
+    assert!(
+      SyncAFoldAttributes { module_name: "tokio".into(), is_async: true, ignored: true, new_attrs: vec![] }
+        .process_str("Numbers: [synca::match];", Span::call_site())
+        .is_err()
+    );
   }
 
   fn process(s: &str) -> (String, String) {
     (
-      SyncAFoldAttributes { is_async: true, ignored: true, new_attrs: vec![] }
-        .process_str(s.into()),
-      SyncAFoldAttributes { is_async: false, ignored: true, new_attrs: vec![] }
-        .process_str(s.into())
+      SyncAFoldAttributes { module_name: "tokio".into(), is_async: true, ignored: true, new_attrs: vec![] }
+        .process_str(s, Span::call_site())
+        .unwrap(),
+      SyncAFoldAttributes { module_name: "sync".into(), is_async: false, ignored: true, new_attrs: vec![] }
+        .process_str(s, Span::call_site())
+        .unwrap()
     )
   }
 }
\ No newline at end of file
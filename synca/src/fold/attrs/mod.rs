@@ -1,7 +1,11 @@
 mod attr;
+#[allow(clippy::module_inception)]
 mod attrs;
+mod cfg;
 mod docs;
+mod replace;
 
 pub use attr::*;
 pub use attrs::*;
-pub use docs::*;
\ No newline at end of file
+pub use cfg::CfgPredicate;
+pub use replace::args_placeholder;
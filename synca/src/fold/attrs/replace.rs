@@ -0,0 +1,132 @@
+use proc_macro2::{Group, TokenStream, TokenTree};
+use syn::{Attribute, Ident, Meta};
+
+use crate::{replace::is_placeholder, SyncAFold};
+
+impl SyncAFold {
+  /// Finds attributes that replace the attribute
+  ///
+  /// Rules are checked in order: the exact attribute, the attribute path
+  /// (`#[tokio::test] => #[test]` matches `#[tokio::test(flavor = "multi_thread")]`)
+  /// and the path with bound arguments (`#[tokio::test($args)] => #[test_log::test($args)]`).
+  /// Among patterns the first declared wins.
+  pub fn replace_attribute(&self, attr: &Attribute) -> Option<Vec<Attribute>> {
+    let get = |key: &Attribute| self.attributes.iter().find(|x| x.0 == *key).map(|x| x.1.clone());
+    if let Some(x) = get(attr) {
+      return Some(x);
+    }
+
+    let mut key = attr.clone();
+    key.meta = Meta::Path(attr.path().clone());
+    if let Some(x) = get(&key) {
+      return Some(x);
+    }
+
+    let args = match &attr.meta {
+      Meta::Path(_) => TokenStream::new(),
+      Meta::List(x) => x.tokens.clone(),
+      Meta::NameValue(_) => return None,
+    };
+
+    self.attributes.iter().find_map(|(pattern, target)| {
+      let name = args_placeholder(pattern)?;
+      if pattern.style != attr.style || pattern.path() != attr.path() {
+        return None;
+      }
+
+      Some(target.iter().map(|x| substitute_args(x, &name, &args)).collect())
+    })
+  }
+}
+
+/// `#[tokio::test($args)]` => `__synca_args`
+pub fn args_placeholder(attr: &Attribute) -> Option<Ident> {
+  let list = match &attr.meta {
+    Meta::List(x) => x,
+    _ => return None,
+  };
+
+  let mut iter = list.tokens.clone().into_iter();
+  match (iter.next(), iter.next()) {
+    (Some(TokenTree::Ident(x)), None) if is_placeholder(&x) => Some(x),
+    _ => None,
+  }
+}
+
+/// Replaces the placeholder in arguments of the attribute, `#[test($args)]` without arguments is `#[test]`
+fn substitute_args(attr: &Attribute, name: &Ident, args: &TokenStream) -> Attribute {
+  let mut result = attr.clone();
+  if let Meta::List(list) = &result.meta {
+    result.meta = match args.is_empty() && args_placeholder(attr).as_ref() == Some(name) {
+      true => Meta::Path(list.path.clone()),
+      false => {
+        let mut list = list.clone();
+        list.tokens = substitute_tokens(list.tokens, name, args);
+        Meta::List(list)
+      },
+    };
+  }
+
+  result
+}
+
+fn substitute_tokens(tokens: TokenStream, name: &Ident, args: &TokenStream) -> TokenStream {
+  tokens
+    .into_iter()
+    .flat_map(|tt| match tt {
+      TokenTree::Ident(x) if &x == name => args.clone(),
+      TokenTree::Group(g) => {
+        let mut group = Group::new(g.delimiter(), substitute_tokens(g.stream(), name, args));
+        group.set_span(g.span());
+        TokenTree::Group(group).into()
+      },
+      tt => tt.into(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::ToTokens;
+  use syn::{parse_quote, Attribute};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn replace_attribute() {
+    let fold = SyncAFold {
+      is_async: false,
+      attributes: vec![
+        (parse_quote!(#[tokio::test]), vec![parse_quote!(#[test])]),
+        (parse_quote!(#[async_trait::async_trait]), vec![]),
+        (
+          parse_quote!(#[tokio::main(__synca_args)]),
+          vec![parse_quote!(#[inline]), parse_quote!(#[custom::main(__synca_args)])]
+        ),
+        (parse_quote!(#[tokio::main(__synca_other)]), vec![parse_quote!(#[other::main(__synca_other)])]),
+      ],
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let replace = |attr: Attribute| {
+      fold
+        .replace_attribute(&attr)
+        .map(|x| x.iter().map(|x| x.to_token_stream().to_string()).collect::<Vec<_>>())
+    };
+
+    assert_eq!(replace(parse_quote!(#[custom])), None);
+    assert_eq!(replace(parse_quote!(#[tokio::test])), Some(vec!["# [test]".into()]));
+    assert_eq!(
+      replace(parse_quote!(#[tokio::test(flavor = "multi_thread")])),
+      Some(vec!["# [test]".into()])
+    );
+    assert_eq!(replace(parse_quote!(#[async_trait::async_trait(?Send)])), Some(vec![]));
+    assert_eq!(
+      replace(parse_quote!(#[tokio::main(flavor = "current_thread")])),
+      Some(vec!["# [inline]".into(), "# [custom :: main (flavor = \"current_thread\")]".into()])
+    );
+    assert_eq!(
+      replace(parse_quote!(#[tokio::main])),
+      Some(vec!["# [inline]".into(), "# [custom :: main]".into()])
+    );
+  }
+}
@@ -0,0 +1,146 @@
+use syn::{punctuated::Punctuated, Attribute};
+
+use crate::{CfgPredicate, SyncAFold};
+
+/// Nodes that may carry `#[synca::cfg(..)]`
+pub trait Attrs {
+  fn attrs(&self) -> &[Attribute];
+}
+
+macro_rules! impl_attrs {
+  ($($ty: ty),*) => {
+    $(impl Attrs for $ty {
+      fn attrs(&self) -> &[Attribute] {
+        &self.attrs
+      }
+    })*
+  };
+}
+
+macro_rules! impl_enum_attrs {
+  ($ty: ident, [$($variant: ident),*]) => {
+    impl Attrs for syn::$ty {
+      fn attrs(&self) -> &[Attribute] {
+        match self {
+          $(syn::$ty::$variant(x) => &x.attrs,)*
+          #[allow(unreachable_patterns)]
+          _ => &[],
+        }
+      }
+    }
+  };
+}
+
+impl_attrs!(
+  syn::Arm, syn::ConstParam, syn::Field, syn::FieldPat, syn::FieldValue,
+  syn::LifetimeParam, syn::PatType, syn::Receiver, syn::TypeParam, syn::Variant
+);
+
+impl_enum_attrs!(Item, [
+  Const, Enum, ExternCrate, Fn, ForeignMod, Impl, Macro, Mod, Static, Struct, Trait, TraitAlias, Type,
+  Union, Use
+]);
+impl_enum_attrs!(ImplItem, [Const, Fn, Type, Macro]);
+impl_enum_attrs!(TraitItem, [Const, Fn, Type, Macro]);
+impl_enum_attrs!(ForeignItem, [Fn, Static, Type, Macro]);
+impl_enum_attrs!(FnArg, [Receiver, Typed]);
+impl_enum_attrs!(GenericParam, [Lifetime, Type, Const]);
+impl_enum_attrs!(Pat, [
+  Const, Ident, Lit, Macro, Or, Paren, Path, Range, Reference, Rest, Slice, Struct, Tuple, TupleStruct,
+  Type, Wild
+]);
+impl_enum_attrs!(Expr, [
+  Array, Assign, Async, Await, Binary, Block, Break, Call, Cast, Closure, Const, Continue, Field,
+  ForLoop, Group, If, Index, Infer, Let, Lit, Loop, Macro, Match, MethodCall, Paren, Path, Range,
+  Reference, Repeat, Return, Struct, Try, TryBlock, Tuple, Unary, Unsafe, While, Yield
+]);
+
+impl Attrs for syn::Stmt {
+  fn attrs(&self) -> &[Attribute] {
+    match self {
+      syn::Stmt::Local(x) => &x.attrs,
+      syn::Stmt::Item(x) => x.attrs(),
+      syn::Stmt::Expr(x, _) => x.attrs(),
+      syn::Stmt::Macro(x) => &x.attrs,
+    }
+  }
+}
+
+/// Containers of nodes that are removed by `#[synca::cfg(..)]`
+pub trait Cfg {
+  fn retain_cfg(&mut self, fold: &SyncAFold);
+}
+
+impl<T: Attrs> Cfg for Vec<T> {
+  fn retain_cfg(&mut self, fold: &SyncAFold) {
+    self.retain(|x| fold.is_cfg_enabled(x.attrs()));
+  }
+}
+
+impl<T: Attrs, P: Default> Cfg for Punctuated<T, P> {
+  fn retain_cfg(&mut self, fold: &SyncAFold) {
+    if self.iter().all(|x| fold.is_cfg_enabled(x.attrs())) {
+      return;
+    }
+
+    // `(a, #[synca::cfg(sync)] b,)` => `(a,)`
+    let trailing = self.trailing_punct();
+    let mut result: Punctuated<T, P> = std::mem::take(self)
+      .into_iter()
+      .filter(|x| fold.is_cfg_enabled(x.attrs()))
+      .collect();
+    if trailing && !result.is_empty() {
+      result.push_punct(P::default());
+    }
+
+    *self = result;
+  }
+}
+
+impl SyncAFold {
+  /// All `#[synca::cfg(..)]` attributes of the node match the module
+  ///
+  /// Malformed attributes keep the node, they are reported by [`crate::SyncAFoldAttributes`].
+  pub fn is_cfg_enabled(&self, attrs: &[Attribute]) -> bool {
+    attrs
+      .iter()
+      .filter(|x| is_synca_cfg(x))
+      .filter_map(|x| x.parse_args::<CfgPredicate>().ok())
+      .all(|x| x.matches(self))
+  }
+}
+
+fn is_synca_cfg(attr: &Attribute) -> bool {
+  let segments = &attr.path().segments;
+
+  segments.len() == 2 && segments[0].ident == "synca" && segments[1].ident == "cfg"
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::ToTokens;
+  use syn::{parse_quote, Expr, ItemStruct};
+
+  use crate::{fold::cfg::Cfg, SyncAFold};
+
+  #[test]
+  fn retain_cfg() {
+    let fold = SyncAFold::new("sync", parse_quote!(feature = "sync"));
+
+    let mut tuple: syn::ExprTuple = parse_quote!((a, #[synca::cfg(tokio)] b,));
+    tuple.elems.retain_cfg(&fold);
+    assert_eq!(tuple.to_token_stream().to_string(), quote::quote!((a,)).to_string());
+
+    let mut call: syn::ExprCall = parse_quote!(f(#[synca::cfg(sync)] a, #[synca::cfg(tokio)] b));
+    call.args.retain_cfg(&fold);
+    let expected: Expr = parse_quote!(f(#[synca::cfg(sync)] a));
+    assert_eq!(call.to_token_stream().to_string(), expected.to_token_stream().to_string());
+
+    let mut item: ItemStruct = parse_quote!(struct A { a: i32, #[synca::cfg(tokio)] b: i32 });
+    if let syn::Fields::Named(x) = &mut item.fields {
+      x.named.retain_cfg(&fold);
+    }
+    let expected: ItemStruct = parse_quote!(struct A { a: i32 });
+    assert_eq!(item.to_token_stream().to_string(), expected.to_token_stream().to_string());
+  }
+}
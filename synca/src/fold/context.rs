@@ -0,0 +1,146 @@
+use proc_macro2::{Group, Literal, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{parse_quote, Item, LitBool, Macro};
+
+use crate::SyncAFold;
+
+impl SyncAFold {
+  /// Expansion of `synca::module_name!()`, `synca::is_async!()` or `synca::select!`
+  pub fn synca_macro(&self, mac: &Macro) -> Option<syn::Result<TokenStream>> {
+    self.context_macro(mac).or_else(|| self.select_macro(mac))
+  }
+
+  /// `synca::module_name!()` => `"tokio"`, `synca::is_async!()` => `true`
+  fn context_macro(&self, mac: &Macro) -> Option<syn::Result<TokenStream>> {
+    let value = match &mac.path {
+      x if *x == parse_quote!(synca::module_name) => self.module_name_literal().into_token_stream(),
+      x if *x == parse_quote!(synca::is_async) => self.is_async_literal().into_token_stream(),
+      _ => return None,
+    };
+
+    match mac.tokens.is_empty() {
+      true => Some(Ok(value)),
+      false => Some(Err(syn::Error::new_spanned(&mac.tokens, "SyncA expected no arguments"))),
+    }
+  }
+
+  /// Synca macros inside arguments of other macros
+  pub fn context_tokens(&mut self, tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut result: Vec<TokenTree> = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+      let expanded = match &tokens[i..] {
+        [
+          TokenTree::Ident(synca), TokenTree::Punct(a), TokenTree::Punct(b),
+          TokenTree::Ident(_), TokenTree::Punct(bang), TokenTree::Group(_), ..
+        ] if synca == "synca" && a.as_char() == ':' && b.as_char() == ':' && bang.as_char() == '!' => 
+          syn::parse2::<Macro>(tokens[i..i + 6].iter().cloned().collect())
+            .ok()
+            .and_then(|mac| Some((self.synca_macro(&mac)?, mac))),
+        _ => None,
+      };
+
+      match (expanded, &tokens[i]) {
+        // `synca::select!` keeps spans of the arm
+        (Some((Ok(x), mac)), _) if mac.path.segments[1].ident == "select" => {
+          result.extend(self.context_tokens(x));
+          i += 6;
+          continue;
+        },
+        (Some((Ok(x), _)), tt) => {
+          result.extend(x.into_iter().map(|mut x| {
+            x.set_span(tt.span());
+            x
+          }));
+          i += 6;
+          continue;
+        },
+        (Some((Err(e), mac)), _) => {
+          self.errors.push(syn::Error::new_spanned(mac, e.to_string()));
+          result.extend(tokens[i..i + 6].iter().cloned());
+          i += 6;
+          continue;
+        },
+        (None, TokenTree::Group(g)) => {
+          let mut group = Group::new(g.delimiter(), self.context_tokens(g.stream()));
+          group.set_span(g.span());
+          result.push(TokenTree::Group(group));
+        },
+        (None, tt) => result.push(tt.clone()),
+      }
+
+      i += 1;
+    }
+
+    result.into_iter().collect()
+  }
+
+  /// Items injected by `mode!()`
+  pub fn mode_items(&self) -> Vec<Item> {
+    let (name, is_async) = (self.module_name_literal(), self.is_async_literal());
+
+    vec![
+      parse_quote!(
+        /// Name and mode of the generated module
+        pub struct Mode;
+      ),
+      parse_quote!(
+        impl Mode {
+          pub const NAME: &'static str = #name;
+          pub const IS_ASYNC: bool = #is_async;
+        }
+      ),
+    ]
+  }
+
+  fn module_name_literal(&self) -> Literal {
+    Literal::string(&self.module_name)
+  }
+
+  fn is_async_literal(&self) -> LitBool {
+    LitBool::new(self.is_async, proc_macro2::Span::call_site())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::{quote, ToTokens};
+  use syn::{fold::Fold, parse_quote, ItemFn};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn context_macros() {
+    let mut fold = SyncAFold { is_async: false, ..SyncAFold::new("sync", parse_quote!(feature = "sync")) };
+    let expected: ItemFn = parse_quote!(
+      fn agent() -> String {
+        info!("{} {}", "sync", false);
+        custom!(x, "sync");
+        format!("my_crate/{}", "sync")
+      }
+    );
+
+    assert_eq!(
+      fold.fold_item_fn(parse_quote!(
+        async fn agent() -> String {
+          info!("{} {}", synca::module_name!(), synca::is_async!());
+          custom!(synca::select! { async => x.await, _ => x }, synca::select! { sync => synca::module_name!() });
+          format!("my_crate/{}", synca::module_name!())
+        }
+      )).to_token_stream().to_string(),
+      expected.to_token_stream().to_string()
+    );
+    assert_eq!(
+      fold.mode_items().iter().map(|x| x.to_token_stream().to_string()).collect::<Vec<_>>(),
+      vec![
+        quote!(#[doc = r" Name and mode of the generated module"] pub struct Mode;).to_string(),
+        quote!(impl Mode {
+          pub const NAME: &'static str = "sync";
+          pub const IS_ASYNC: bool = false;
+        }).to_string(),
+      ]
+    );
+  }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+  ext::IdentExt,
+  parse::{discouraged::Speculative, ParseStream, Parser},
+  Block, Expr, Item, Lifetime, Lit, Meta, Pat, Path, Stmt, Type, Visibility
+};
+
+/// Rules of a `macro_rules!` definition
+///
+/// Supports fragments, nested repetitions and `$crate`, the expansion is not hygienic.
+pub struct MacroRules {
+  rules: Vec<(Vec<Matcher>, TokenStream)>,
+}
+
+enum Matcher {
+  Token(String),
+  Group(Delimiter, Vec<Matcher>),
+  Fragment(String, String),
+  Repeat(Vec<Matcher>, Option<String>, char),
+}
+
+#[derive(Clone)]
+enum Binding {
+  Tokens(TokenStream),
+  Seq(Vec<Binding>),
+}
+
+type Bindings = HashMap<String, Binding>;
+
+impl MacroRules {
+  /// Parses the body of `macro_rules! name { (matcher) => { transcriber }; .. }`
+  pub fn parse(tokens: TokenStream) -> syn::Result<Self> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut rules = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+      match (&tokens[i], tokens.get(i + 1), tokens.get(i + 2), tokens.get(i + 3)) {
+        (
+          TokenTree::Group(matcher),
+          Some(TokenTree::Punct(eq)),
+          Some(TokenTree::Punct(gt)),
+          Some(TokenTree::Group(transcriber)),
+        ) if eq.as_char() == '=' && gt.as_char() == '>' => {
+          rules.push((matchers(matcher.stream())?, transcriber.stream()));
+          i += 4;
+        },
+        (TokenTree::Punct(x), ..) if x.as_char() == ';' => i += 1,
+        (x, ..) => return Err(syn::Error::new(x.span(), "SyncA expected macro rule (..) => { .. }")),
+      }
+    }
+
+    Ok(MacroRules { rules })
+  }
+
+  /// Transcribes the first rule that matches the input
+  pub fn expand(&self, input: TokenStream, span: Span) -> syn::Result<TokenStream> {
+    for (matcher, transcriber) in self.rules.iter() {
+      let mut bindings = Bindings::new();
+      let parser = |input: ParseStream| -> syn::Result<()> {
+        match_seq(matcher, input, &mut bindings)?;
+        match input.is_empty() {
+          true => Ok(()),
+          false => Err(input.error("unexpected token")),
+        }
+      };
+
+      if parser.parse2(input.clone()).is_ok() {
+        return transcribe(transcriber.clone(), &bindings);
+      }
+    }
+
+    Err(syn::Error::new(span, "SyncA: no rules of the local macro matched this invocation"))
+  }
+}
+
+fn matchers(tokens: TokenStream) -> syn::Result<Vec<Matcher>> {
+  let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+  let mut result = vec![];
+  let mut i = 0;
+
+  while i < tokens.len() {
+    match (&tokens[i], tokens.get(i + 1)) {
+      (TokenTree::Punct(p), Some(TokenTree::Ident(name))) if p.as_char() == '$' => {
+        match (tokens.get(i + 2), tokens.get(i + 3)) {
+          (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(kind))) if colon.as_char() == ':' => {
+            result.push(Matcher::Fragment(name.to_string(), kind.to_string()));
+            i += 4;
+          },
+          _ => return Err(syn::Error::new(name.span(), "SyncA expected fragment $name:kind")),
+        }
+      },
+      (TokenTree::Punct(p), Some(TokenTree::Group(g))) if p.as_char() == '$' => {
+        let (sep, op, len) = repetition(&tokens[i + 2..])
+          .ok_or_else(|| syn::Error::new(g.span(), "SyncA expected repetition operator"))?;
+        result.push(Matcher::Repeat(matchers(g.stream())?, sep, op));
+        i += 2 + len;
+      },
+      (TokenTree::Group(g), _) => {
+        result.push(Matcher::Group(g.delimiter(), matchers(g.stream())?));
+        i += 1;
+      },
+      (x, _) => {
+        result.push(Matcher::Token(x.to_string()));
+        i += 1;
+      },
+    }
+  }
+
+  Ok(result)
+}
+
+/// `,*` => `(Some(","), '*', 2)`
+fn repetition(tokens: &[TokenTree]) -> Option<(Option<String>, char, usize)> {
+  let op = |x: Option<&TokenTree>| match x {
+    Some(TokenTree::Punct(p)) if matches!(p.as_char(), '*' | '+' | '?') => Some(p.as_char()),
+    _ => None,
+  };
+
+  match op(tokens.first()) {
+    Some(x) => Some((None, x, 1)),
+    None => Some((Some(tokens.first()?.to_string()), op(tokens.get(1))?, 2)),
+  }
+}
+
+fn match_seq(matchers: &[Matcher], input: ParseStream, bindings: &mut Bindings) -> syn::Result<()> {
+  for matcher in matchers {
+    match_one(matcher, input, bindings)?;
+  }
+
+  Ok(())
+}
+
+fn match_one(matcher: &Matcher, input: ParseStream, bindings: &mut Bindings) -> syn::Result<()> {
+  match matcher {
+    Matcher::Token(x) => {
+      let tt: TokenTree = input.parse()?;
+      match tt.to_string() == *x {
+        true => Ok(()),
+        false => Err(syn::Error::new(tt.span(), format!("expected {}", x))),
+      }
+    },
+    Matcher::Group(delimiter, matchers) => match input.parse()? {
+      TokenTree::Group(g) if g.delimiter() == *delimiter => {
+        let parser = |input: ParseStream| -> syn::Result<()> {
+          match_seq(matchers, input, bindings)?;
+          match input.is_empty() {
+            true => Ok(()),
+            false => Err(input.error("unexpected token")),
+          }
+        };
+
+        parser.parse2(g.stream())
+      },
+      tt => Err(syn::Error::new(tt.span(), "unexpected token")),
+    },
+    Matcher::Fragment(name, kind) => {
+      bindings.insert(name.clone(), Binding::Tokens(fragment(kind, input)?));
+      Ok(())
+    },
+    Matcher::Repeat(matchers, sep, op) => {
+      let mut seq: Vec<Bindings> = vec![];
+      let is_done = |seq: &Vec<Bindings>| input.is_empty() || (*op == '?' && seq.len() == 1);
+      while !is_done(&seq) {
+        let fork = input.fork();
+        let mut item = Bindings::new();
+        if match_seq(matchers, &fork, &mut item).is_err() {
+          break;
+        }
+
+        input.advance_to(&fork);
+        seq.push(item);
+
+        if let Some(sep) = sep {
+          let fork = input.fork();
+          match fork.parse::<TokenTree>() {
+            Ok(tt) if tt.to_string() == *sep => input.advance_to(&fork),
+            _ => break,
+          }
+        }
+      }
+
+      if *op == '+' && seq.is_empty() {
+        return Err(input.error("expected at least one repetition"));
+      }
+
+      let mut names = vec![];
+      fragment_names(matchers, &mut names);
+      for name in names {
+        let items = seq.iter().filter_map(|x| x.get(&name).cloned()).collect();
+        bindings.insert(name, Binding::Seq(items));
+      }
+
+      Ok(())
+    },
+  }
+}
+
+fn fragment_names(matchers: &[Matcher], names: &mut Vec<String>) {
+  for matcher in matchers {
+    match matcher {
+      Matcher::Fragment(name, _) => names.push(name.clone()),
+      Matcher::Group(_, x) | Matcher::Repeat(x, _, _) => fragment_names(x, names),
+      Matcher::Token(_) => { },
+    }
+  }
+}
+
+fn fragment(kind: &str, input: ParseStream) -> syn::Result<TokenStream> {
+  Ok(match kind {
+    // keeps the precedence of the expression (`$a * 2`)
+    "expr" => TokenTree::Group(Group::new(Delimiter::None, input.parse::<Expr>()?.into_token_stream())).into(),
+    "ty" => input.parse::<Type>()?.into_token_stream(),
+    "ident" => Ident::parse_any(input)?.into_token_stream(),
+    "path" => input.parse::<Path>()?.into_token_stream(),
+    "pat" => Pat::parse_multi_with_leading_vert(input)?.into_token_stream(),
+    "pat_param" => Pat::parse_single(input)?.into_token_stream(),
+    "stmt" => input.parse::<Stmt>()?.into_token_stream(),
+    "block" => input.parse::<Block>()?.into_token_stream(),
+    "item" => input.parse::<Item>()?.into_token_stream(),
+    "literal" => input.parse::<Lit>()?.into_token_stream(),
+    "lifetime" => input.parse::<Lifetime>()?.into_token_stream(),
+    "meta" => input.parse::<Meta>()?.into_token_stream(),
+    "vis" => input.parse::<Visibility>()?.into_token_stream(),
+    "tt" => input.parse::<TokenTree>()?.into_token_stream(),
+    x => return Err(input.error(format!("SyncA: unsupported fragment kind {}", x))),
+  })
+}
+
+fn transcribe(tokens: TokenStream, bindings: &Bindings) -> syn::Result<TokenStream> {
+  let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+  let mut result = TokenStream::new();
+  let mut i = 0;
+
+  while i < tokens.len() {
+    match (&tokens[i], tokens.get(i + 1)) {
+      (TokenTree::Punct(p), Some(TokenTree::Ident(name))) if p.as_char() == '$' => {
+        match bindings.get(&name.to_string()) {
+          Some(Binding::Tokens(x)) => result.extend(x.clone()),
+          Some(Binding::Seq(_)) =>
+            return Err(syn::Error::new(name.span(), "SyncA: variable is still repeating at this depth")),
+          None if name == "crate" => result.extend(Some(TokenTree::Ident(Ident::new("crate", name.span())))),
+          None => return Err(syn::Error::new(name.span(), format!("SyncA: unknown macro variable {}", name))),
+        }
+        i += 2;
+      },
+      (TokenTree::Punct(p), Some(TokenTree::Group(g))) if p.as_char() == '$' => {
+        let (sep, _, len) = repetition(&tokens[i + 2..])
+          .ok_or_else(|| syn::Error::new(g.span(), "SyncA expected repetition operator"))?;
+        let repeated: Vec<(&String, &Vec<Binding>)> = used_names(g.stream())
+          .iter()
+          .filter_map(|x| bindings.get_key_value(x))
+          .filter_map(|(k, v)| match v {
+            Binding::Seq(x) => Some((k, x)),
+            Binding::Tokens(_) => None,
+          })
+          .collect();
+        let count = repeated.iter().map(|(_, x)| x.len()).max().unwrap_or_default();
+
+        for n in 0..count {
+          if n > 0 {
+            if let Some(sep) = &sep {
+              result.extend(sep.parse::<TokenStream>()?);
+            }
+          }
+
+          let mut inner = bindings.clone();
+          for (name, seq) in repeated.iter() {
+            if let Some(x) = seq.get(n) {
+              inner.insert(name.to_string(), x.clone());
+            }
+          }
+          result.extend(transcribe(g.stream(), &inner)?);
+        }
+        i += 2 + len;
+      },
+      (TokenTree::Group(g), _) => {
+        let mut group = Group::new(g.delimiter(), transcribe(g.stream(), bindings)?);
+        group.set_span(g.span());
+        result.extend(Some(TokenTree::Group(group)));
+        i += 1;
+      },
+      (x, _) => {
+        result.extend(Some(x.clone()));
+        i += 1;
+      },
+    }
+  }
+
+  Ok(result)
+}
+
+fn used_names(tokens: TokenStream) -> Vec<String> {
+  let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+  let mut names = vec![];
+  for (i, tt) in tokens.iter().enumerate() {
+    match (tt, tokens.get(i + 1)) {
+      (TokenTree::Punct(p), Some(TokenTree::Ident(name))) if p.as_char() == '$' => names.push(name.to_string()),
+      (TokenTree::Group(g), _) => names.extend(used_names(g.stream())),
+      _ => { },
+    }
+  }
+
+  names
+}
+
+#[cfg(test)]
+mod tests {
+  use proc_macro2::Span;
+  use quote::quote;
+
+  use super::MacroRules;
+
+  #[test]
+  fn expand() {
+    let rules = MacroRules::parse(quote!(
+      ($c:expr) => { $c.query_one("SQL").await };
+      ($c:expr, $($arg:expr),+ $(,)?) => { $c.query_one("SQL", &[$(&$arg),*]).await };
+      (fn $name:ident -> $ty:ty) => { async fn $name() -> $ty { $crate::get().await } };
+    )).unwrap();
+    let expand = |tokens| rules.expand(tokens, Span::call_site()).map(|x| x.to_string());
+
+    assert_eq!(expand(quote!(client)).unwrap(), quote!(client.query_one("SQL").await).to_string());
+    assert_eq!(
+      expand(quote!(client, 1, "a",)).unwrap(),
+      quote!(client.query_one("SQL", &[&1, &"a"]).await).to_string()
+    );
+    assert_eq!(
+      expand(quote!(fn name -> String)).unwrap(),
+      quote!(async fn name() -> String { crate::get().await }).to_string()
+    );
+    assert!(expand(quote!(fn)).is_err());
+  }
+}
@@ -1,48 +1,151 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use quote::ToTokens;
 use syn::{fold::{self, Fold}, Expr};
 
-use crate::SyncAFoldAttributes;
+use crate::{
+  errors::Errors,
+  fold::{cfg::Cfg, futures::*, patterns::substitute},
+  is_empty_use, macros::MacroPolicy, ImportScope, SyncAFoldAttributes
+};
 
 #[derive(Debug, PartialEq)]
 pub struct SyncAFold {
   pub module_name: String,
   pub is_async: bool,
+  pub keep_async: bool,
+  pub async_replace: bool,
   pub types: HashMap<syn::Type, syn::Type>,
-  pub attributes: HashMap<syn::Attribute, syn::Attribute>,
-  pub cfg: Expr
+  pub type_patterns: Vec<(syn::Type, syn::Type)>,
+  pub paths: HashMap<syn::Path, syn::Path>,
+  pub attributes: Vec<(syn::Attribute, Vec<syn::Attribute>)>,
+  pub idents: HashMap<syn::Ident, syn::Ident>,
+  pub ident_patterns: Vec<(String, String)>,
+  pub definitions: HashSet<syn::Ident>,
+  pub macros: Vec<(syn::Path, MacroPolicy)>,
+  pub local_macros: HashMap<syn::Ident, syn::ItemMacro>,
+  pub expand_depth: usize,
+  pub cfg: Expr,
+  pub imports: Vec<ImportScope>,
+  pub errors: Errors
 }
 
-macro_rules! impl_fold_fn {
-  ($fn_name: ident, $ty: ty) => {
-    fn $fn_name(&mut self, i: $ty) -> $ty {
-      let attrs = SyncAFoldAttributes::new(&self, &i.attrs);
-      if attrs.ignored { 
-        return i;
+impl SyncAFold {
+  pub fn new<T: Into<String>>(module_name: T, cfg: Expr) -> Self {
+    Self {
+      module_name: module_name.into(),
+      is_async: true,
+      keep_async: false,
+      async_replace: false,
+      types: HashMap::new(),
+      type_patterns: vec![],
+      paths: HashMap::new(),
+      attributes: vec![],
+      idents: HashMap::new(),
+      ident_patterns: vec![],
+      definitions: HashSet::new(),
+      macros: vec![],
+      local_macros: HashMap::new(),
+      expand_depth: 0,
+      cfg,
+      imports: vec![],
+      errors: Errors::default()
+    }
+  }
+
+  /// `async`, `.await` and futures are removed: sync module outside of `#[synca::keep_async]`
+  pub fn lowers_async(&self) -> bool {
+    !self.is_async && !self.keep_async
+  }
+
+  /// Types, paths and attributes are replaced: sync module or `#[synca::replace]` of the async module
+  pub fn replaces(&self) -> bool {
+    !self.is_async || self.async_replace
+  }
+
+  fn attrs(&mut self, attrs: &[syn::Attribute]) -> Option<SyncAFoldAttributes> {
+    match SyncAFoldAttributes::new(self, attrs) {
+      Ok(x) => Some(x),
+      Err(e) => {
+        self.errors.push(e);
+        None
       }
+    }
+  }
+}
+
+/// Processes attributes of the node, returns the node untouched if it is ignored
+macro_rules! fold_attrs {
+  ($self: ident, $i: ident) => {{
+    let attrs = match $self.attrs(&$i.attrs) {
+      Some(x) => x,
+      None => return $i,
+    };
+    if attrs.ignored { 
+      return $i;
+    }
+
+    let mut new_i = $i.clone();
+    new_i.attrs = attrs.new_attrs;
+    new_i
+  }};
+}
 
-      let mut new_fn = i.clone();
-      new_fn.attrs = attrs.new_attrs;
-      if !self.is_async {
-        new_fn.sig.asyncness = None;
+/// `impl_fold_fn!(fold_item_fn, syn::ItemFn, block)` also applies `#[synca::body(..)]` to `block`
+macro_rules! impl_fold_fn {
+  ($fn_name: ident, $ty: ty, $body: ident) => {
+    fn $fn_name(&mut self, i: $ty) -> $ty {
+      let mut new_fn = i;
+      if let Some(x) = self.fn_body(&new_fn.attrs) {
+        new_fn.$body = x.into();
       }
 
-      fold::$fn_name(self, new_fn)
+      impl_fold_fn!(@sync self, new_fn, $fn_name)
+    }
+  };
+  ($fn_name: ident, $ty: ty) => {
+    fn $fn_name(&mut self, i: $ty) -> $ty {
+      impl_fold_fn!(@sync self, i, $fn_name)
     }
   };
+  (@sync $self: ident, $i: ident, $fn_name: ident) => {{
+    let mut new_fn = fold_attrs!($self, $i);
+    if $self.lowers_async() {
+      new_fn.sig.asyncness = None;
+      lower_return_type(&mut new_fn.sig.output);
+      lower_future_params(&mut new_fn.sig);
+    }
+
+    fold::$fn_name($self, new_fn)
+  }};
 }
 
+/// `impl_fold_attrs!(fold_expr_call, syn::ExprCall, args)` also removes `args` filtered by `#[synca::cfg]`
 macro_rules! impl_fold_attrs {
-  ($fn_name: ident, $ty: ty) => {
+  ($fn_name: ident, $ty: ty $(, $nodes: ident)?) => {
     fn $fn_name(&mut self, i: $ty) -> $ty {
-      let attrs = SyncAFoldAttributes::new(&self, &i.attrs);
-      if attrs.ignored { 
-        return i;
-      }
+      let new_i = fold_attrs!(self, i);
+      $(let mut new_i = new_i; new_i.$nodes.retain_cfg(self);)?
       
-      let mut new_i = i.clone();
-      new_i.attrs = attrs.new_attrs;
+      fold::$fn_name(self, new_i)
+    }
+  };
+}
+
+macro_rules! impl_fold_path {
+  ($fn_name: ident, $ty: ty $(, $nodes: ident)?) => {
+    fn $fn_name(&mut self, i: $ty) -> $ty {
+      let mut new_i = fold_attrs!(self, i);
+      $(new_i.$nodes.retain_cfg(self);)?
+      if self.replaces() {
+        match &new_i.qself {
+          Some(qself) => if let Some(x) = self.resolve_qualified_path(qself, &new_i.path) {
+            (new_i.qself, new_i.path) = (Some(x.0), x.1);
+          },
+          None => if let Some(path) = self.resolve_path(&new_i.path, true) {
+            new_i.path = path;
+          },
+        }
+      }
       
       fold::$fn_name(self, new_i)
     }
@@ -51,71 +154,334 @@ macro_rules! impl_fold_attrs {
 
 impl Fold for SyncAFold {
   fn fold_type(&mut self, ty: syn::Type) -> syn::Type {
-    if self.is_async {
+    if !self.replaces() {
       return fold::fold_type(self, ty);
     }
 
     match &ty {
       syn::Type::Path(path) => {
-        match self.types.get(&ty) {
-          Some(new_ty) => new_ty.clone(),
-          None => fold::fold_type(self, syn::Type::Path(path.clone())),
+        if let Some(new_ty) = self.types.get(&ty) {
+          return new_ty.clone();
         }
+
+        let pattern = self
+          .match_pattern(&ty)
+          .map(|x| (x, false))
+          .or_else(|| {
+            let imported = self.imported_type(&ty)?;
+            self.match_pattern(&imported).map(|x| (x, true))
+          });
+        if let Some(((target, bindings), is_imported)) = pattern {
+          let bindings = bindings
+            .into_iter()
+            .map(|(k, v)| (k, self.fold_type(v)))
+            .collect();
+          let replaced = substitute(&target, &bindings);
+
+          return match is_imported {
+            true => self.keep_imported_name(&ty, replaced),
+            false => replaced,
+          };
+        }
+
+        let mut new_path = path.clone();
+        match &new_path.qself {
+          Some(qself) => if let Some(x) = self.resolve_qualified_path(qself, &new_path.path) {
+            (new_path.qself, new_path.path) = (Some(x.0), x.1);
+          },
+          None => if let Some(x) = self.resolve_path(&new_path.path, false) {
+            new_path.path = x;
+          },
+        }
+
+        fold::fold_type(self, syn::Type::Path(new_path))
       },
       _ => fold::fold_type(self, ty),
     }
   }
 
-  fn fold_macro(&mut self, mac: syn::Macro) -> syn::Macro {
-    if self.is_async {
-      return fold::fold_macro(self, mac);
+  fn fold_trait_bound(&mut self, bound: syn::TraitBound) -> syn::TraitBound {
+    let mut new_bound = bound;
+    if self.lowers_async() {
+      lower_fn_bound(&mut new_bound);
+    }
+    if self.replaces() {
+      if let Some(x) = self.resolve_path(&new_bound.path, false) {
+        new_bound.path = x;
+      }
     }
 
-    let macro_str = fold::fold_macro(self, mac)
-      .to_token_stream()
-      .to_string()
-      .replace(". await", "")
-      .replace(".await", "");
-      
-    syn::parse_str(&macro_str).unwrap()
+    fold::fold_trait_bound(self, new_bound)
+  }
+
+  fn fold_ident(&mut self, i: syn::Ident) -> syn::Ident {
+    self.rename_ident(&i).unwrap_or(i)
+  }
+
+  fn fold_path(&mut self, i: syn::Path) -> syn::Path {
+    if !self.is_external_path(&i) {
+      return fold::fold_path(self, i);
+    }
+
+    // Segments of other crates are not renamed, generic arguments are folded
+    let mut new_i = i;
+    for segment in new_i.segments.iter_mut() {
+      segment.arguments = self.fold_path_arguments(std::mem::take(&mut segment.arguments));
+    }
+
+    new_i
+  }
+
+  fn fold_macro(&mut self, mac: syn::Macro) -> syn::Macro {
+    let mut mac = mac;
+    mac.tokens = self.fold_macro_tokens(&mac);
+
+    fold::fold_macro(self, mac)
   }
 
   fn fold_expr(&mut self, exp: Expr) -> Expr {
-    if self.is_async {
-      return fold::fold_expr(self, exp);
+    self.scoped(exp, |this, exp| {
+      if let Expr::Macro(x) = &exp {
+        if let Some(expanded) = x.attrs.is_empty().then(|| this.expand_expr(&x.mac)).flatten() {
+          return this.fold_expanded(|this| this.fold_expr(expanded));
+        }
+      }
+
+      if !this.lowers_async() {
+        return fold::fold_expr(this, exp);
+      }
+
+      match exp {
+        Expr::Await(e) => this.fold_expr(*e.base),
+        Expr::Async(e) => this.fold_expr(Expr::Block(syn::ExprBlock {
+          attrs: e.attrs,
+          label: None,
+          block: e.block,
+        })),
+        _ => match boxed_future(&exp) {
+          Some(x) => this.fold_expr(x),
+          None => fold::fold_expr(this, exp),
+        },
+      }
+    })
+  }
+
+  fn fold_expr_closure(&mut self, i: syn::ExprClosure) -> syn::ExprClosure {
+    let mut new_i = fold_attrs!(self, i);
+    new_i.inputs.retain_cfg(self);
+    if self.lowers_async() {
+      new_i.asyncness = None;
+    }
+
+    fold::fold_expr_closure(self, new_i)
+  }
+
+  fn fold_impl_item_type(&mut self, i: syn::ImplItemType) -> syn::ImplItemType {
+    let mut new_i = fold_attrs!(self, i);
+    if self.lowers_async() {
+      if let Some(x) = future_output(&new_i.ty) {
+        new_i.ty = x;
+      }
+    }
+
+    fold::fold_impl_item_type(self, new_i)
+  }
+
+  fn fold_trait_item_type(&mut self, i: syn::TraitItemType) -> syn::TraitItemType {
+    let mut new_i = fold_attrs!(self, i);
+    if self.lowers_async() {
+      if let Some((_, x)) = &mut new_i.default {
+        if let Some(output) = future_output(x) {
+          *x = output;
+        }
+      }
+
+      // `type Fut: Future<Output = T> + Send` => `type Fut: Send`
+      if new_i.bounds.iter().any(is_future_bound) {
+        new_i.bounds = without_future(new_i.bounds);
+        if new_i.bounds.is_empty() {
+          new_i.colon_token = None;
+        }
+      }
     }
 
-    match exp {
-      Expr::Await(e) => self.fold_expr(*e.base),
-      Expr::Async(e) => self.fold_expr(Expr::Block(syn::ExprBlock {
-        attrs: e.attrs,
-        label: None,
-        block: e.block,
-      })),
-      _ => fold::fold_expr(self, exp),
+    fold::fold_trait_item_type(self, new_i)
+  }
+
+  fn fold_item_use(&mut self, i: syn::ItemUse) -> syn::ItemUse {
+    let mut new_i = fold_attrs!(self, i);
+    if self.replaces() {
+      new_i = self.replace_use(new_i);
     }
+
+    let tree = new_i.tree.clone();
+    let mut new_i = fold::fold_item_use(self, new_i);
+    new_i.tree = match new_i.leading_colon {
+      Some(_) => tree,
+      None => self.fold_use_root(tree),
+    };
+
+    new_i
   }
 
-  impl_fold_fn!(fold_item_fn, syn::ItemFn);
-  impl_fold_fn!(fold_impl_item_fn, syn::ImplItemFn);
-  impl_fold_fn!(fold_trait_item_fn, syn::TraitItemFn);
+  fn fold_item_impl(&mut self, i: syn::ItemImpl) -> syn::ItemImpl {
+    let mut new_i = fold_attrs!(self, i);
+    new_i.items.retain_cfg(self);
+    if self.replaces() {
+      if let Some((_, path, _)) = &mut new_i.trait_ {
+        if let Some(x) = self.resolve_path(path, false) {
+          *path = x;
+        }
+      }
+    }
+
+    fold::fold_item_impl(self, new_i)
+  }
+
+  fn fold_item(&mut self, i: syn::Item) -> syn::Item {
+    self.scoped(i, |this, i| {
+      if let syn::Item::Macro(x) = &i {
+        if let Some(items) = x.attrs.is_empty().then(|| this.expand_items(&x.mac)).flatten() {
+          let items: Vec<_> = this.fold_expanded(|this| items.into_iter().map(|x| this.fold_item(x)).collect());
+          return syn::Item::Verbatim(quote::quote!(#(#items)*));
+        }
+      }
+
+      fold::fold_item(this, i)
+    })
+  }
+
+  fn fold_impl_item(&mut self, i: syn::ImplItem) -> syn::ImplItem {
+    self.scoped(i, fold::fold_impl_item)
+  }
+
+  fn fold_trait_item(&mut self, i: syn::TraitItem) -> syn::TraitItem {
+    self.scoped(i, fold::fold_trait_item)
+  }
+
+  fn fold_stmt(&mut self, i: syn::Stmt) -> syn::Stmt {
+    self.scoped(i, |this, i| {
+      if let syn::Stmt::Macro(x) = &i {
+        if let Some(mut stmts) = x.attrs.is_empty().then(|| this.expand_stmts(&x.mac)).flatten() {
+          // `query!(c);` => `c.query("SQL");`
+          if let (Some(semi), Some(syn::Stmt::Expr(_, last_semi @ None))) = (x.semi_token, stmts.last_mut()) {
+            *last_semi = Some(semi);
+          }
+
+          let mut stmts: Vec<_> = this.fold_expanded(|this| stmts.into_iter().map(|x| this.fold_stmt(x)).collect());
+          return match stmts.len() {
+            1 => stmts.remove(0),
+            _ => syn::Stmt::Item(syn::Item::Verbatim(quote::quote!(#(#stmts)*))),
+          };
+        }
+      }
+
+      fold::fold_stmt(this, i)
+    })
+  }
+
+  fn fold_item_mod(&mut self, i: syn::ItemMod) -> syn::ItemMod {
+    let mut new_i = fold_attrs!(self, i);
+    if let Some((_, items)) = &mut new_i.content {
+      items.retain_cfg(self);
+    }
+
+    let items = new_i.content.as_ref().map(|x| x.1.as_slice()).unwrap_or_default();
+    self.collect_local_macros(items);
+    self.collect_definitions(items);
+    self.imports.push(ImportScope::module(items));
+    let mut new_i = fold::fold_item_mod(self, new_i);
+    self.imports.pop();
+
+    if let Some((_, items)) = &mut new_i.content {
+      items.retain(|x| !is_empty_use(x));
+    }
+
+    new_i
+  }
+
+  fn fold_file(&mut self, i: syn::File) -> syn::File {
+    let mut new_i = fold_attrs!(self, i);
+    new_i.items.retain_cfg(self);
+
+    self.collect_local_macros(&new_i.items);
+    self.imports.push(ImportScope::module(&new_i.items));
+    let mut new_i = fold::fold_file(self, new_i);
+    self.imports.pop();
+
+    new_i.items.retain(|x| !is_empty_use(x));
+
+    new_i
+  }
+
+  fn fold_block(&mut self, i: syn::Block) -> syn::Block {
+    let mut new_i = i;
+    new_i.stmts.retain_cfg(self);
+
+    self.imports.push(ImportScope::block(&new_i.stmts));
+    let mut new_i = fold::fold_block(self, new_i);
+    self.imports.pop();
+
+    new_i.stmts.retain(|x| !matches!(x, syn::Stmt::Item(item) if is_empty_use(item)));
+
+    new_i
+  }
+
+  fn fold_fields_named(&mut self, i: syn::FieldsNamed) -> syn::FieldsNamed {
+    let mut new_i = i;
+    new_i.named.retain_cfg(self);
+
+    fold::fold_fields_named(self, new_i)
+  }
+
+  fn fold_fields_unnamed(&mut self, i: syn::FieldsUnnamed) -> syn::FieldsUnnamed {
+    let mut new_i = i;
+    new_i.unnamed.retain_cfg(self);
+
+    fold::fold_fields_unnamed(self, new_i)
+  }
+
+  fn fold_generics(&mut self, i: syn::Generics) -> syn::Generics {
+    let mut new_i = i;
+    new_i.params.retain_cfg(self);
+    if new_i.params.is_empty() {
+      (new_i.lt_token, new_i.gt_token) = (None, None);
+    }
+
+    fold::fold_generics(self, new_i)
+  }
+
+  fn fold_signature(&mut self, i: syn::Signature) -> syn::Signature {
+    let mut new_i = i;
+    new_i.inputs.retain_cfg(self);
+
+    fold::fold_signature(self, new_i)
+  }
+
+  impl_fold_fn!(fold_item_fn, syn::ItemFn, block);
+  impl_fold_fn!(fold_impl_item_fn, syn::ImplItemFn, block);
+  impl_fold_fn!(fold_trait_item_fn, syn::TraitItemFn, default);
   impl_fold_fn!(fold_foreign_item_fn, syn::ForeignItemFn);
   
+  impl_fold_path!(fold_expr_path, syn::ExprPath);
+  impl_fold_path!(fold_expr_struct, syn::ExprStruct, fields);
+  impl_fold_path!(fold_pat_struct, syn::PatStruct, fields);
+  impl_fold_path!(fold_pat_tuple_struct, syn::PatTupleStruct);
+
   impl_fold_attrs!(fold_arm, syn::Arm);
   impl_fold_attrs!(fold_bare_fn_arg, syn::BareFnArg);
   impl_fold_attrs!(fold_bare_variadic, syn::BareVariadic);
   impl_fold_attrs!(fold_const_param, syn::ConstParam);
   impl_fold_attrs!(fold_derive_input, syn::DeriveInput);
-  impl_fold_attrs!(fold_expr_array, syn::ExprArray);
+  impl_fold_attrs!(fold_expr_array, syn::ExprArray, elems);
   impl_fold_attrs!(fold_expr_assign, syn::ExprAssign);
   impl_fold_attrs!(fold_expr_async, syn::ExprAsync);
   impl_fold_attrs!(fold_expr_await, syn::ExprAwait);
   impl_fold_attrs!(fold_expr_binary, syn::ExprBinary);
   impl_fold_attrs!(fold_expr_block, syn::ExprBlock);
   impl_fold_attrs!(fold_expr_break, syn::ExprBreak);
-  impl_fold_attrs!(fold_expr_call, syn::ExprCall);
+  impl_fold_attrs!(fold_expr_call, syn::ExprCall, args);
   impl_fold_attrs!(fold_expr_cast, syn::ExprCast);
-  impl_fold_attrs!(fold_expr_closure, syn::ExprClosure);
   impl_fold_attrs!(fold_expr_const, syn::ExprConst);
   impl_fold_attrs!(fold_expr_continue, syn::ExprContinue);
   impl_fold_attrs!(fold_expr_field, syn::ExprField);
@@ -128,18 +494,16 @@ impl Fold for SyncAFold {
   impl_fold_attrs!(fold_expr_lit, syn::ExprLit);
   impl_fold_attrs!(fold_expr_loop, syn::ExprLoop);
   impl_fold_attrs!(fold_expr_macro, syn::ExprMacro);
-  impl_fold_attrs!(fold_expr_match, syn::ExprMatch);
-  impl_fold_attrs!(fold_expr_method_call, syn::ExprMethodCall);
+  impl_fold_attrs!(fold_expr_match, syn::ExprMatch, arms);
+  impl_fold_attrs!(fold_expr_method_call, syn::ExprMethodCall, args);
   impl_fold_attrs!(fold_expr_paren, syn::ExprParen);
-  impl_fold_attrs!(fold_expr_path, syn::ExprPath);
   impl_fold_attrs!(fold_expr_range, syn::ExprRange);
   impl_fold_attrs!(fold_expr_reference, syn::ExprReference);
   impl_fold_attrs!(fold_expr_repeat, syn::ExprRepeat);
   impl_fold_attrs!(fold_expr_return, syn::ExprReturn);
-  impl_fold_attrs!(fold_expr_struct, syn::ExprStruct);
   impl_fold_attrs!(fold_expr_try, syn::ExprTry);
   impl_fold_attrs!(fold_expr_try_block, syn::ExprTryBlock);
-  impl_fold_attrs!(fold_expr_tuple, syn::ExprTuple);
+  impl_fold_attrs!(fold_expr_tuple, syn::ExprTuple, elems);
   impl_fold_attrs!(fold_expr_unary, syn::ExprUnary);
   impl_fold_attrs!(fold_expr_unsafe, syn::ExprUnsafe);
   impl_fold_attrs!(fold_expr_while, syn::ExprWhile);
@@ -147,27 +511,22 @@ impl Fold for SyncAFold {
   impl_fold_attrs!(fold_field, syn::Field);
   impl_fold_attrs!(fold_field_pat, syn::FieldPat);
   impl_fold_attrs!(fold_field_value, syn::FieldValue);
-  impl_fold_attrs!(fold_file, syn::File);
   impl_fold_attrs!(fold_foreign_item_macro, syn::ForeignItemMacro);
   impl_fold_attrs!(fold_foreign_item_static, syn::ForeignItemStatic);
   impl_fold_attrs!(fold_foreign_item_type, syn::ForeignItemType);
   impl_fold_attrs!(fold_impl_item_const, syn::ImplItemConst);
   impl_fold_attrs!(fold_impl_item_macro, syn::ImplItemMacro);
-  impl_fold_attrs!(fold_impl_item_type, syn::ImplItemType);
   impl_fold_attrs!(fold_item_const, syn::ItemConst);
-  impl_fold_attrs!(fold_item_enum, syn::ItemEnum);
+  impl_fold_attrs!(fold_item_enum, syn::ItemEnum, variants);
   impl_fold_attrs!(fold_item_extern_crate, syn::ItemExternCrate);
-  impl_fold_attrs!(fold_item_foreign_mod, syn::ItemForeignMod);
-  impl_fold_attrs!(fold_item_impl, syn::ItemImpl);
+  impl_fold_attrs!(fold_item_foreign_mod, syn::ItemForeignMod, items);
   impl_fold_attrs!(fold_item_macro, syn::ItemMacro);
-  impl_fold_attrs!(fold_item_mod, syn::ItemMod);
   impl_fold_attrs!(fold_item_static, syn::ItemStatic);
   impl_fold_attrs!(fold_item_struct, syn::ItemStruct);
-  impl_fold_attrs!(fold_item_trait, syn::ItemTrait);
+  impl_fold_attrs!(fold_item_trait, syn::ItemTrait, items);
   impl_fold_attrs!(fold_item_trait_alias, syn::ItemTraitAlias);
   impl_fold_attrs!(fold_item_type, syn::ItemType);
   impl_fold_attrs!(fold_item_union, syn::ItemUnion);
-  impl_fold_attrs!(fold_item_use, syn::ItemUse);
   impl_fold_attrs!(fold_lifetime_param, syn::LifetimeParam);
   impl_fold_attrs!(fold_local, syn::Local);
   impl_fold_attrs!(fold_pat_ident, syn::PatIdent);
@@ -176,16 +535,14 @@ impl Fold for SyncAFold {
   impl_fold_attrs!(fold_pat_reference, syn::PatReference);
   impl_fold_attrs!(fold_pat_rest, syn::PatRest);
   impl_fold_attrs!(fold_pat_slice, syn::PatSlice);
-  impl_fold_attrs!(fold_pat_struct, syn::PatStruct);
   impl_fold_attrs!(fold_pat_tuple, syn::PatTuple);
-  impl_fold_attrs!(fold_pat_tuple_struct, syn::PatTupleStruct);
   impl_fold_attrs!(fold_pat_type, syn::PatType);
   impl_fold_attrs!(fold_pat_wild, syn::PatWild);
   impl_fold_attrs!(fold_receiver, syn::Receiver);
   impl_fold_attrs!(fold_stmt_macro, syn::StmtMacro);
   impl_fold_attrs!(fold_trait_item_const, syn::TraitItemConst);
   impl_fold_attrs!(fold_trait_item_macro, syn::TraitItemMacro);
-  impl_fold_attrs!(fold_trait_item_type, syn::TraitItemType);
+  impl_fold_attrs!(fold_type_param, syn::TypeParam);
   impl_fold_attrs!(fold_variadic, syn::Variadic);
   impl_fold_attrs!(fold_variant, syn::Variant);
 }
@@ -197,7 +554,7 @@ mod tests {
   use quote::ToTokens;
   use syn::{fold::Fold, parse_quote};
 
-  use crate::SyncAFold;
+  use crate::{errors::Errors, SyncAFold};
   
   macro_rules! assert_as_str {
     (
@@ -219,6 +576,8 @@ mod tests {
         fold_sync.$fn_name($a).to_token_stream().to_string(),
         b_sync_typed.to_token_stream().to_string()
       );
+      assert_eq!(fold_async.errors, Errors::default());
+      assert_eq!(fold_sync.errors, Errors::default());
     }
   }
 
@@ -247,6 +606,140 @@ mod tests {
       parse_quote!(&mut tokio_postgres::Client),
       parse_quote!(&mut postgres::Client)
     );
+
+    assert_as_str!(
+      fold_type, 
+      syn::Type,
+      parse_quote!(Vec<tokio::io::BufReader<tokio::fs::File>>),
+      parse_quote!(Vec<tokio::io::BufReader<tokio::fs::File>>),
+      parse_quote!(Vec<std::io::BufReader<std::fs::File>>)
+    );
+  }
+
+  #[test]
+  fn fold_type_pattern() {
+    assert_as_str!(
+      fold_type, 
+      syn::Type,
+      parse_quote!(Arc<tokio::sync::Mutex<tokio_postgres::Client>>),
+      parse_quote!(Arc<tokio::sync::Mutex<tokio_postgres::Client>>),
+      parse_quote!(Arc<std::sync::Mutex<postgres::Client>>)
+    );
+
+    assert_as_str!(
+      fold_expr, 
+      syn::Expr,
+      parse_quote!(tokio::sync::Mutex::new(tokio::sync::Mutex::<u8>::new(0))),
+      parse_quote!(tokio::sync::Mutex::new(tokio::sync::Mutex::<u8>::new(0))),
+      parse_quote!(std::sync::Mutex::new(std::sync::Mutex::<u8>::new(0)))
+    );
+
+    assert_as_str!(
+      fold_item_mod, 
+      syn::ItemMod,
+      parse_quote!(
+        mod my_mod {
+          use tokio::sync::mpsc::Sender;
+
+          struct Channel { tx: Sender<u8> }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use tokio::sync::mpsc::Sender;
+
+          struct Channel { tx: Sender<u8> }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use std::sync::mpsc::SyncSender as Sender;
+
+          struct Channel { tx: Sender<u8> }
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn fold_trait_bound() {
+    assert_as_str!(
+      fold_generics, 
+      syn::Generics,
+      parse_quote!(<R: tokio::io::BufRead + Send>),
+      parse_quote!(<R: tokio::io::BufRead + Send>),
+      parse_quote!(<R: std::io::BufRead + Send>)
+    );
+
+    assert_as_str!(
+      fold_item_trait, 
+      syn::ItemTrait,
+      parse_quote!(trait Reader: tokio::io::BufRead where Self: tokio::io::Seek { }),
+      parse_quote!(trait Reader: tokio::io::BufRead where Self: tokio::io::Seek { }),
+      parse_quote!(trait Reader: std::io::BufRead where Self: std::io::Seek { })
+    );
+
+    assert_as_str!(
+      fold_type, 
+      syn::Type,
+      parse_quote!((Box<dyn tokio::io::Write + Send>, impl futures::Stream<Item = u8>)),
+      parse_quote!((Box<dyn tokio::io::Write + Send>, impl futures::Stream<Item = u8>)),
+      parse_quote!((Box<dyn std::io::Write + Send>, impl Iterator<Item = u8>))
+    );
+
+    assert_as_str!(
+      fold_item_impl, 
+      syn::ItemImpl,
+      parse_quote!(impl tokio::io::Write for Calc { }),
+      parse_quote!(impl tokio::io::Write for Calc { }),
+      parse_quote!(impl std::io::Write for Calc { })
+    );
+
+    assert_as_str!(
+      fold_expr, 
+      syn::Expr,
+      parse_quote!(<Calc as tokio::io::Write>::flush(&mut calc)),
+      parse_quote!(<Calc as tokio::io::Write>::flush(&mut calc)),
+      parse_quote!(<Calc as std::io::Write>::flush(&mut calc))
+    );
+  }
+
+  #[test]
+  fn fold_expr_path() {
+    assert_as_str!(
+      fold_expr, 
+      syn::Expr,
+      parse_quote!(tokio_postgres::Client::connect(&conn_str, tokio_postgres::NoTls)),
+      parse_quote!(tokio_postgres::Client::connect(&conn_str, tokio_postgres::NoTls)),
+      parse_quote!(postgres::Client::connect(&conn_str, postgres::NoTls))
+    );
+
+    assert_as_str!(
+      fold_expr, 
+      syn::Expr,
+      parse_quote!(crate::tokio::Calc { client }),
+      parse_quote!(crate::tokio::Calc { client }),
+      parse_quote!(crate::sync::Calc { client })
+    );
+  }
+
+  #[test]
+  fn fold_pat() {
+    assert_as_str!(
+      fold_arm, 
+      syn::Arm,
+      parse_quote!(tokio_postgres::error::SqlState::UNIQUE_VIOLATION => 1),
+      parse_quote!(tokio_postgres::error::SqlState::UNIQUE_VIOLATION => 1),
+      parse_quote!(postgres::error::SqlState::UNIQUE_VIOLATION => 1)
+    );
+
+    assert_as_str!(
+      fold_pat, 
+      syn::Pat,
+      parse_quote!(crate::tokio::Calc { client, .. }),
+      parse_quote!(crate::tokio::Calc { client, .. }),
+      parse_quote!(crate::sync::Calc { client, .. })
+    );
   }
 
   #[test]
@@ -258,6 +751,13 @@ mod tests {
       parse_quote!(assert_eq!(dao.answer().await, 42)),
       parse_quote!(assert_eq!(dao.answer(), 42))
     );
+    assert_as_str!(
+      fold_macro, 
+      syn::Macro,
+      parse_quote!(println!("{} .await", dao.answer().await?)),
+      parse_quote!(println!("{} .await", dao.answer().await?)),
+      parse_quote!(println!("{} .await", dao.answer()?))
+    );
   }
 
   #[test]
@@ -271,6 +771,214 @@ mod tests {
     );
   }
 
+  #[test]
+  fn future_return_types() {
+    assert_as_str!(
+      fold_item_mod,
+      syn::ItemMod,
+      parse_quote!(
+        mod my_mod {
+          trait Storage {
+            type Fut: Future<Output = u8> + Send;
+
+            fn get(&self) -> impl Future<Output = Vec<u8>> + Send + '_;
+            fn put(&self, data: Vec<u8>) -> BoxFuture<'_, ()>;
+          }
+
+          impl Storage for Memory {
+            type Fut = Pin<Box<dyn Future<Output = u8> + Send>>;
+
+            fn get(&self) -> impl Future<Output = Vec<u8>> + Send + '_ {
+              async move { self.data.clone() }
+            }
+
+            fn put(&self, data: Vec<u8>) -> BoxFuture<'_, ()> {
+              Box::pin(async move { self.write(data).await; })
+            }
+          }
+
+          fn count() -> Pin<Box<dyn Future<Output = usize>>> {
+            async { 1 }.boxed_local()
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          trait Storage {
+            type Fut: Future<Output = u8> + Send;
+
+            fn get(&self) -> impl Future<Output = Vec<u8>> + Send + '_;
+            fn put(&self, data: Vec<u8>) -> BoxFuture<'_, ()>;
+          }
+
+          impl Storage for Memory {
+            type Fut = Pin<Box<dyn Future<Output = u8> + Send>>;
+
+            fn get(&self) -> impl Future<Output = Vec<u8>> + Send + '_ {
+              async move { self.data.clone() }
+            }
+
+            fn put(&self, data: Vec<u8>) -> BoxFuture<'_, ()> {
+              Box::pin(async move { self.write(data).await; })
+            }
+          }
+
+          fn count() -> Pin<Box<dyn Future<Output = usize>>> {
+            async { 1 }.boxed_local()
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          trait Storage {
+            type Fut: Send;
+
+            fn get(&self) -> Vec<u8>;
+            fn put(&self, data: Vec<u8>);
+          }
+
+          impl Storage for Memory {
+            type Fut = u8;
+
+            fn get(&self) -> Vec<u8> {
+              { self.data.clone() }
+            }
+
+            fn put(&self, data: Vec<u8>) {
+              { self.write(data); }
+            }
+          }
+
+          fn count() -> usize {
+            { 1 }
+          }
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn async_closures() {
+    assert_as_str!(
+      fold_item_fn,
+      syn::ItemFn,
+      parse_quote!(
+        async fn retry<F, Fut, T>(f: F) -> T
+        where
+          F: Fn() -> Fut,
+          Fut: Future<Output = Option<T>>
+        {
+          loop {
+            if let Some(x) = f().await {
+              return x;
+            }
+          }
+        }
+      ),
+      parse_quote!(
+        async fn retry<F, Fut, T>(f: F) -> T
+        where
+          F: Fn() -> Fut,
+          Fut: Future<Output = Option<T>>
+        {
+          loop {
+            if let Some(x) = f().await {
+              return x;
+            }
+          }
+        }
+      ),
+      parse_quote!(
+        fn retry<F, T>(f: F) -> T
+        where
+          F: Fn() -> Option<T>
+        {
+          loop {
+            if let Some(x) = f() {
+              return x;
+            }
+          }
+        }
+      )
+    );
+    assert_as_str!(
+      fold_item_fn,
+      syn::ItemFn,
+      parse_quote!(
+        async fn transaction(client: &Client, f: impl AsyncFnOnce(&Client) -> u8) -> u8 {
+          let run = async move |x: u8| f(client).await + x;
+          let get = move || async move { run(1).await };
+
+          get().await
+        }
+      ),
+      parse_quote!(
+        async fn transaction(client: &Client, f: impl AsyncFnOnce(&Client) -> u8) -> u8 {
+          let run = async move |x: u8| f(client).await + x;
+          let get = move || async move { run(1).await };
+
+          get().await
+        }
+      ),
+      parse_quote!(
+        fn transaction(client: &Client, f: impl FnOnce(&Client) -> u8) -> u8 {
+          let run = move |x: u8| f(client) + x;
+          let get = move || { run(1) };
+
+          get()
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn future_params() {
+    assert_as_str!(
+      fold_item_fn,
+      syn::ItemFn,
+      parse_quote!(
+        async fn with_retry<F: std::future::Future<Output = R>, R>(f: impl Fn() -> F) -> R {
+          f().await
+        }
+      ),
+      parse_quote!(
+        async fn with_retry<F: std::future::Future<Output = R>, R>(f: impl Fn() -> F) -> R {
+          f().await
+        }
+      ),
+      parse_quote!(
+        fn with_retry<R>(f: impl Fn() -> R) -> R {
+          f()
+        }
+      )
+    );
+    assert_as_str!(
+      fold_item_fn,
+      syn::ItemFn,
+      parse_quote!(
+        async fn logged<F>(name: &str, fut: F) -> F::Output where F: Future + Send {
+          let result = fut.await;
+          println!("{}", name);
+          result
+        }
+      ),
+      parse_quote!(
+        async fn logged<F>(name: &str, fut: F) -> F::Output where F: Future + Send {
+          let result = fut.await;
+          println!("{}", name);
+          result
+        }
+      ),
+      parse_quote!(
+        fn logged<F>(name: &str, fut: F) -> F where F: Send {
+          let result = fut;
+          println!("{}", name);
+          result
+        }
+      )
+    );
+  }
+
   #[test]
   fn macro_impl_fold_fn() {
     assert_as_str!(
@@ -354,6 +1062,151 @@ mod tests {
     );
   }
 
+  #[test]
+  fn fold_item_use() {
+    assert_as_str!(
+      fold_item_mod, 
+      syn::ItemMod,
+      parse_quote!(
+        mod my_mod {
+          use tokio_postgres::{Client, NoTls};
+          use tokio::io::AsyncReadExt;
+
+          fn test() {
+            use tokio::fs::File;
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use tokio_postgres::{Client, NoTls};
+          use tokio::io::AsyncReadExt;
+
+          fn test() {
+            use tokio::fs::File;
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use postgres::{Client, NoTls};
+
+          fn test() {
+            use std::fs::File;
+          }
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn fold_ident() {
+    let (_, fold_sync) = synca_fold();
+    let mut fold = SyncAFold {
+      idents: HashMap::from([
+        (parse_quote!(AsyncClient), parse_quote!(Client)),
+      ]),
+      ident_patterns: vec![("*_async".into(), "*".into())],
+      ..fold_sync
+    };
+    let expected: syn::ItemMod = parse_quote!(
+      mod my_mod {
+        use tokio_tungstenite::connect_async;
+
+        pub struct Client {
+          inner: postgres::Client,
+        }
+
+        impl Client {
+          pub fn connect(url: &str) -> Self {
+            let inner = tokio_tungstenite::connect_async(url);
+            println!("{}", Self::query(&inner));
+            Client { inner }
+          }
+
+          fn query(inner: &postgres::Client) -> String {
+            inner.to_string_async()
+          }
+        }
+      }
+    );
+
+    assert_eq!(
+      fold.fold_item_mod(parse_quote!(
+        mod my_mod {
+          use tokio_tungstenite::connect_async;
+
+          pub struct AsyncClient {
+            inner_async: tokio_postgres::Client,
+          }
+
+          impl AsyncClient {
+            pub async fn connect_async(url: &str) -> Self {
+              let inner_async = tokio_tungstenite::connect_async(url).await;
+              println!("{}", Self::query_async(&inner_async).await);
+              AsyncClient { inner_async }
+            }
+
+            async fn query_async(inner_async: &tokio_postgres::Client) -> String {
+              inner_async.to_string_async().await
+            }
+          }
+        }
+      )).to_token_stream().to_string(),
+      expected.to_token_stream().to_string()
+    );
+  }
+
+  #[test]
+  fn imports() {
+    assert_as_str!(
+      fold_item_mod, 
+      syn::ItemMod,
+      parse_quote!(
+        mod my_mod {
+          use tokio_postgres as pg;
+          use tokio_postgres::Client;
+
+          fn client() -> pg::Client { 
+            Client::connect()
+          }
+
+          mod tests {
+            fn client() -> pg::Client { }
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use tokio_postgres as pg;
+          use tokio_postgres::Client;
+
+          fn client() -> pg::Client { 
+            Client::connect()
+          }
+
+          mod tests {
+            fn client() -> pg::Client { }
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use tokio_postgres as pg;
+          use postgres::Client;
+
+          fn client() -> postgres::Client { 
+            Client::connect()
+          }
+
+          mod tests {
+            fn client() -> pg::Client { }
+          }
+        }
+      )
+    );
+  }
+
   #[test]
   fn synca_only() {
     assert_as_str!(
@@ -381,8 +1234,6 @@ mod tests {
           
           async fn name() -> String {
             return "tokio_42".into();
-            #[cfg(all(feature = "tokio", not(feature = "tokio")))]
-            return "sync_42".into();
           }
         }
       ),
@@ -392,8 +1243,6 @@ mod tests {
           type Client = postgres::Client;
           
           fn name() -> String {
-            #[cfg(all(feature = "sync", not(feature = "sync")))]
-            return "tokio_42".into();
             return "sync_42".into();
           }
         }
@@ -401,30 +1250,132 @@ mod tests {
     );
   }
 
+  #[test]
+  fn synca_cfg_removal() {
+    assert_as_str!(
+      fold_item_mod, 
+      syn::ItemMod,
+      parse_quote!(
+        mod my_mod {
+          #[synca::cfg(sync)]
+          use std::fs;
+
+          struct Conn<#[synca::cfg(tokio)] R: Runtime> {
+            #[synca::cfg(tokio)]
+            runtime: R,
+            client: tokio_postgres::Client,
+          }
+
+          async fn connect(#[synca::cfg(tokio)] runtime: &Runtime, url: &str) -> Conn {
+            let parts = (url, #[synca::cfg(sync)] fs::read(url));
+            let urls = vec![url, #[synca::cfg(tokio)] "tokio"];
+            let open = |#[synca::cfg(tokio)] runtime: &Runtime, url: &str| url;
+            println!("{} {}", url, #[synca::cfg(sync)] "sync");
+            match url {
+              #[synca::cfg(sync)]
+              "" => panic!(),
+              _ => connect_to(#[synca::cfg(tokio)] runtime, url).await,
+            }
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          struct Conn<R: Runtime> {
+            runtime: R,
+            client: tokio_postgres::Client,
+          }
+
+          async fn connect(runtime: &Runtime, url: &str) -> Conn {
+            let parts = (url,);
+            let urls = vec![url, "tokio"];
+            let open = |runtime: &Runtime, url: &str| url;
+            println!("{} {}", url);
+            match url {
+              _ => connect_to(runtime, url).await,
+            }
+          }
+        }
+      ),
+      parse_quote!(
+        mod my_mod {
+          use std::fs;
+
+          struct Conn {
+            client: postgres::Client,
+          }
+
+          fn connect(url: &str) -> Conn {
+            let parts = (url, fs::read(url));
+            let urls = vec![url];
+            let open = |url: &str| url;
+            println!("{} {}", url, "sync");
+            match url {
+              "" => panic!(),
+              _ => connect_to(url),
+            }
+          }
+        }
+      )
+    );
+  }
+
   fn synca_fold() -> (SyncAFold, SyncAFold) {
     let types: HashMap<syn::Type, syn::Type> = HashMap::from([
       (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
       (parse_quote!(tokio_postgres::NoTls), parse_quote!(postgres::NoTls)),
+      (parse_quote!(tokio_postgres::error::SqlState), parse_quote!(postgres::error::SqlState)),
+      (parse_quote!(crate::tokio::Calc), parse_quote!(crate::sync::Calc)),
+      (parse_quote!(tokio::fs), parse_quote!(std::fs)),
+      (parse_quote!(tokio::io::AsyncReadExt), parse_quote!(_)),
     ]);
-    let attributes: HashMap<syn::Attribute, syn::Attribute> = HashMap::from([
-      (parse_quote!(#[tokio::test]), parse_quote!(#[test])),
+    let type_patterns: Vec<(syn::Type, syn::Type)> = vec![
+      (parse_quote!(futures::Stream<Item = __synca_T>), parse_quote!(Iterator<Item = __synca_T>)),
+      (parse_quote!(tokio::sync::Mutex<__synca_T>), parse_quote!(std::sync::Mutex<__synca_T>)),
+      (
+        parse_quote!(tokio::sync::mpsc::Sender<__synca_T>), 
+        parse_quote!(std::sync::mpsc::SyncSender<__synca_T>)
+      ),
+    ];
+    let paths: HashMap<syn::Path, syn::Path> = HashMap::from([
+      (parse_quote!(tokio::io), parse_quote!(std::io)),
     ]);
+    let attributes: Vec<(syn::Attribute, Vec<syn::Attribute>)> = vec![
+      (parse_quote!(#[tokio::test]), vec![parse_quote!(#[test])]),
+    ];
     
     (
       SyncAFold {
-        module_name: "tokio".into(),
-        is_async: true,
         types: types.clone(),
+        type_patterns: type_patterns.clone(),
+        paths: paths.clone(),
         attributes: attributes.clone(),
-        cfg: parse_quote!(feature = "tokio")
+        ..SyncAFold::new("tokio", parse_quote!(feature = "tokio"))
       },
       SyncAFold {
-        module_name: "sync".into(),
         is_async: false,
         types,
+        type_patterns,
+        paths,
         attributes,
-        cfg: parse_quote!(feature = "sync")
+        ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
       }
     )
   }
+
+  #[test]
+  fn errors() {
+    let (mut fold_async, _) = synca_fold();
+    let item: syn::ItemFn = parse_quote!(
+      /// [synca::sync]
+      fn my_fn() { 
+        #[synca::cfg(tokio, sync)]
+        let x = 42;
+      }
+    );
+
+    fold_async.fold_item_fn(item);
+
+    assert!(fold_async.errors.to_compile_error().to_string().contains("not closed"));
+  }
 }
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use syn::{
+  fold::{self, Fold}, parse_quote, punctuated::Punctuated, Expr, FnArg, GenericArgument, GenericParam,
+  Ident, Path, PathArguments, ReturnType, Signature, TraitBound, Type, TypeParamBound, TypePath,
+  WherePredicate
+};
+
+/// Output of the future type
+///
+/// - `impl Future<Output = T> + Send + '_` => `T`
+/// - `BoxFuture<'_, T>`, `LocalBoxFuture<'_, T>` => `T`
+/// - `Pin<Box<dyn Future<Output = T> + Send>>` => `T`
+pub fn future_output(ty: &Type) -> Option<Type> {
+  match ty {
+    Type::Group(x) => future_output(&x.elem),
+    Type::Paren(x) => future_output(&x.elem),
+    Type::ImplTrait(x) => bounds_output(x.bounds.iter()),
+    Type::Path(TypePath { qself: None, path }) => {
+      let last = path.segments.last()?;
+      match last.ident.to_string().as_str() {
+        "BoxFuture" | "LocalBoxFuture" => type_arguments(path).pop(),
+        "Pin" => match type_arguments(path).as_slice() {
+          [Type::Path(TypePath { qself: None, path })] if is_last(path, "Box") =>
+            match type_arguments(path).as_slice() {
+              [Type::TraitObject(x)] => bounds_output(x.bounds.iter()),
+              _ => None,
+            },
+          _ => None,
+        },
+        _ => None,
+      }
+    },
+    _ => None,
+  }
+}
+
+/// `-> impl Future<Output = T>` => `-> T`
+pub fn lower_return_type(output: &mut ReturnType) {
+  if let ReturnType::Type(_, ty) = output {
+    match future_output(ty) {
+      Some(x) if x == parse_quote!(()) => *output = ReturnType::Default,
+      Some(x) => **ty = x,
+      None => { },
+    }
+  }
+}
+
+/// `Future<Output = T>`
+pub fn is_future_bound(bound: &TypeParamBound) -> bool {
+  matches!(bound, TypeParamBound::Trait(x) if is_last(&x.path, "Future"))
+}
+
+/// `AsyncFn(u8) -> T` => `Fn(u8) -> T`, `Fn() -> BoxFuture<'_, T>` => `Fn() -> T`
+pub fn lower_fn_bound(bound: &mut TraitBound) {
+  if let Some(last) = bound.path.segments.last_mut() {
+    if let PathArguments::Parenthesized(args) = &mut last.arguments {
+      let name = last.ident.to_string();
+      if let Some(x) = name.strip_prefix("Async").filter(|x| x.starts_with("Fn")) {
+        last.ident = Ident::new(x, last.ident.span());
+      }
+
+      lower_return_type(&mut args.output);
+    }
+  }
+}
+
+/// Lowers generic params bounded by `Future` and future arguments
+///
+/// - `fn retry<F: Fn() -> Fut, Fut: Future<Output = T>, T>` => `fn retry<F: Fn() -> T, T>`
+/// - `fn run<F: Future>(f: F) -> F::Output` => `fn run<F>(f: F) -> F`
+/// - `fn run(f: impl Future<Output = T>)` => `fn run(f: T)`
+pub fn lower_future_params(sig: &mut Signature) {
+  for input in sig.inputs.iter_mut() {
+    if let FnArg::Typed(x) = input {
+      if let Some(output) = future_output(&x.ty) {
+        *x.ty = output;
+      }
+    }
+  }
+
+  let mut params = FutureParams { values: HashMap::new(), outputs: HashMap::new() };
+  let type_params: Vec<Ident> = sig.generics.type_params().map(|x| x.ident.clone()).collect();
+  let mut add = |ident: &Ident, output: Option<Type>| match output {
+    Some(x) => {
+      params.values.insert(ident.clone(), x.clone());
+      params.outputs.insert(ident.clone(), x);
+    },
+    // the value of the param is the output
+    None => if !params.values.contains_key(ident) {
+      params.outputs.insert(ident.clone(), parse_quote!(#ident));
+    },
+  };
+
+  for param in sig.generics.type_params() {
+    if let Some(output) = future_bound(param.bounds.iter()) {
+      add(&param.ident, output);
+    }
+  }
+  for predicate in sig.generics.where_clause.iter().flat_map(|x| x.predicates.iter()) {
+    if let WherePredicate::Type(x) = predicate {
+      match (type_ident(&x.bounded_ty), future_bound(x.bounds.iter())) {
+        (Some(ident), Some(output)) if type_params.contains(ident) => add(ident, output),
+        _ => { },
+      }
+    }
+  }
+
+  if params.outputs.is_empty() {
+    return;
+  }
+
+  let generics = &mut sig.generics;
+  generics.params = std::mem::take(&mut generics.params)
+    .into_iter()
+    .filter_map(|x| match x {
+      GenericParam::Type(x) if params.values.contains_key(&x.ident) => None,
+      GenericParam::Type(mut x) if params.outputs.contains_key(&x.ident) => {
+        x.bounds = without_future(x.bounds);
+        if x.bounds.is_empty() {
+          x.colon_token = None;
+        }
+
+        Some(GenericParam::Type(x))
+      },
+      x => Some(x),
+    })
+    .collect();
+  if generics.params.is_empty() {
+    generics.lt_token = None;
+    generics.gt_token = None;
+  }
+
+  if let Some(where_clause) = &mut generics.where_clause {
+    where_clause.predicates = std::mem::take(&mut where_clause.predicates)
+      .into_iter()
+      .filter_map(|x| match x {
+        WherePredicate::Type(mut x) => match type_ident(&x.bounded_ty) {
+          Some(ident) if params.values.contains_key(ident) => None,
+          Some(ident) if params.outputs.contains_key(ident) => {
+            x.bounds = without_future(x.bounds);
+            (!x.bounds.is_empty()).then_some(WherePredicate::Type(x))
+          },
+          _ => Some(WherePredicate::Type(x)),
+        },
+        x => Some(x),
+      })
+      .collect();
+    if where_clause.predicates.is_empty() {
+      generics.where_clause = None;
+    }
+  }
+
+  *sig = params.fold_signature(sig.clone());
+}
+
+/// Replaces removed params with their outputs and `F::Output` with the output of `F`
+struct FutureParams {
+  values: HashMap<Ident, Type>,
+  outputs: HashMap<Ident, Type>,
+}
+
+impl Fold for FutureParams {
+  fn fold_type(&mut self, ty: Type) -> Type {
+    let output = match &ty {
+      Type::Path(TypePath { qself: None, path }) => match path.get_ident() {
+        Some(x) => self.values.get(x),
+        None if path.segments.len() == 2 && path.segments[1].ident == "Output" =>
+          self.outputs.get(&path.segments[0].ident),
+        None => None,
+      },
+      // `<F as Future>::Output`
+      Type::Path(TypePath { qself: Some(qself), path }) 
+        if path.segments.last().map(|x| x.ident == "Output") == Some(true) =>
+          type_ident(&qself.ty).and_then(|x| self.outputs.get(x)),
+      _ => None,
+    };
+
+    match output {
+      Some(x) => x.clone(),
+      None => fold::fold_type(self, ty),
+    }
+  }
+}
+
+/// The async block of `Box::pin(async { .. })` or `async { .. }.boxed()`
+pub fn boxed_future(expr: &Expr) -> Option<Expr> {
+  match expr {
+    Expr::Call(x) => match (&*x.func, x.args.first()) {
+      (Expr::Path(f), Some(arg @ Expr::Async(_))) if x.args.len() == 1 && is_box_pin(&f.path) =>
+        Some(arg.clone()),
+      _ => None,
+    },
+    Expr::MethodCall(x) if x.args.is_empty() && (x.method == "boxed" || x.method == "boxed_local") =>
+      match &*x.receiver {
+        receiver @ Expr::Async(_) => Some(receiver.clone()),
+        _ => None,
+      },
+    _ => None,
+  }
+}
+
+fn bounds_output<'a, I: Iterator<Item = &'a TypeParamBound>>(bounds: I) -> Option<Type> {
+  future_bound(bounds).map(|x| x.unwrap_or_else(|| parse_quote!(())))
+}
+
+/// `Future<Output = T>` => `Some(Some(T))`, `Future` => `Some(None)`
+fn future_bound<'a, I: Iterator<Item = &'a TypeParamBound>>(bounds: I) -> Option<Option<Type>> {
+  let mut bounds = bounds.filter_map(|x| match x {
+    TypeParamBound::Trait(x) if is_last(&x.path, "Future") => Some(&x.path),
+    _ => None,
+  });
+  let path = bounds.next()?;
+
+  Some(match &path.segments.last()?.arguments {
+    PathArguments::AngleBracketed(x) => x.args.iter().find_map(|x| match x {
+      GenericArgument::AssocType(x) if x.ident == "Output" => Some(x.ty.clone()),
+      _ => None,
+    }),
+    _ => None,
+  })
+}
+
+pub fn without_future<P: Default>(bounds: Punctuated<TypeParamBound, P>) -> Punctuated<TypeParamBound, P> {
+  bounds.into_iter().filter(|x| !is_future_bound(x)).collect()
+}
+
+fn type_ident(ty: &Type) -> Option<&Ident> {
+  match ty {
+    Type::Path(TypePath { qself: None, path }) => path.get_ident(),
+    _ => None,
+  }
+}
+
+fn type_arguments(path: &Path) -> Vec<Type> {
+  match path.segments.last().map(|x| &x.arguments) {
+    Some(PathArguments::AngleBracketed(x)) => x.args.iter().filter_map(|x| match x {
+      GenericArgument::Type(x) => Some(x.clone()),
+      _ => None,
+    }).collect(),
+    _ => vec![],
+  }
+}
+
+fn is_last(path: &Path, ident: &str) -> bool {
+  path.segments.last().map(|x| x.ident == ident).unwrap_or_default()
+}
+
+fn is_box_pin(path: &Path) -> bool {
+  let len = path.segments.len();
+  len >= 2 && path.segments[len - 1].ident == "pin" && path.segments[len - 2].ident == "Box"
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::ToTokens;
+  use syn::{parse_quote, Expr, Signature, TraitBound, Type};
+
+  use super::{boxed_future, future_output, lower_fn_bound, lower_future_params};
+
+  #[test]
+  fn future_output_type() {
+    let output = |ty: Type| future_output(&ty).map(|x| x.to_token_stream().to_string());
+
+    assert_eq!(output(parse_quote!(Vec<u8>)), None);
+    assert_eq!(output(parse_quote!(impl Iterator<Item = u8>)), None);
+    assert_eq!(output(parse_quote!(impl Future<Output = u8> + Send + '_)), Some("u8".into()));
+    assert_eq!(output(parse_quote!(impl std::future::Future)), Some("()".into()));
+    assert_eq!(
+      output(parse_quote!(futures::future::BoxFuture<'a, Result<u8, Error>>)),
+      Some("Result < u8 , Error >".into())
+    );
+    assert_eq!(
+      output(parse_quote!(Pin<Box<dyn Future<Output = String> + Send + 'static>>)),
+      Some("String".into())
+    );
+    assert_eq!(output(parse_quote!(Pin<Box<u8>>)), None);
+  }
+
+  #[test]
+  fn boxed_future_expr() {
+    let inner = |expr: Expr| boxed_future(&expr).map(|x| x.to_token_stream().to_string());
+
+    assert_eq!(inner(parse_quote!(Box::pin(stream))), None);
+    assert_eq!(inner(parse_quote!(Box::pin(async move { 1 }))), Some("async move { 1 }".into()));
+    assert_eq!(inner(parse_quote!(async { 1 }.boxed())), Some("async { 1 }".into()));
+  }
+
+  #[test]
+  fn lower_fn() {
+    let bound = |mut x: TraitBound| {
+      lower_fn_bound(&mut x);
+      x.to_token_stream().to_string()
+    };
+    let sig = |mut x: Signature| {
+      lower_future_params(&mut x);
+      x.to_token_stream().to_string()
+    };
+
+    assert_eq!(bound(parse_quote!(AsyncFnMut(u8) -> T)), "FnMut (u8) -> T");
+    assert_eq!(
+      bound(parse_quote!(for<'a> FnOnce(&'a mut Tx) -> BoxFuture<'a, T>)),
+      "for < 'a > FnOnce (& 'a mut Tx) -> T"
+    );
+    assert_eq!(bound(parse_quote!(Iterator<Item = u8>)), "Iterator < Item = u8 >");
+    assert_eq!(
+      sig(parse_quote!(fn retry<F: Fn() -> Fut, Fut: Future<Output = Result<T, E>>, T, E>(f: F) -> Result<T, E>)),
+      "fn retry < F : Fn () -> Result < T , E > , T , E > (f : F) -> Result < T , E >"
+    );
+    assert_eq!(
+      sig(parse_quote!(fn run<F, Fut>(f: F) where F: FnOnce() -> Fut, Fut: std::future::Future<Output = ()>)),
+      "fn run < F > (f : F) where F : FnOnce () -> ()"
+    );
+    assert_eq!(
+      sig(parse_quote!(fn run<F: Future + Send>(f: F) -> F::Output)),
+      "fn run < F : Send > (f : F) -> F"
+    );
+    assert_eq!(
+      sig(parse_quote!(fn run<F>(f: F) -> <F as Future>::Output where F: futures::Future)),
+      "fn run < F > (f : F) -> F"
+    );
+    assert_eq!(
+      sig(parse_quote!(fn run(f: impl Future<Output = u8> + Send, g: BoxFuture<'_, u16>))),
+      "fn run (f : u8 , g : u16)"
+    );
+  }
+}
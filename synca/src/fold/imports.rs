@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use syn::{Ident, Item, Path, PathArguments, Stmt, Type, TypePath};
+
+use crate::{fold::{paths::set_turbofish, patterns::without_arguments, uses::use_imports}, SyncAFold};
+
+/// Names imported by `use` items of a module or a block
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportScope {
+  pub is_module: bool,
+  pub names: HashMap<Ident, Path>,
+}
+
+impl ImportScope {
+  pub fn module(items: &[Item]) -> Self {
+    let mut scope = ImportScope { is_module: true, names: HashMap::new() };
+    scope.extend(items.iter());
+
+    scope
+  }
+
+  pub fn block(stmts: &[Stmt]) -> Self {
+    let mut scope = ImportScope { is_module: false, names: HashMap::new() };
+    scope.extend(stmts.iter().filter_map(|x| match x {
+      Stmt::Item(item) => Some(item),
+      _ => None,
+    }));
+
+    scope
+  }
+
+  fn extend<'a, I: Iterator<Item = &'a Item>>(&mut self, items: I) {
+    for item in items {
+      if let Item::Use(x) = item {
+        self.names.extend(use_imports(x));
+      }
+    }
+  }
+}
+
+impl SyncAFold {
+  /// Replaces paths directly or through the names imported in the current scope
+  ///
+  /// `use tokio_postgres as pg;` + `pg::Client` => `postgres::Client`
+  pub fn resolve_path(&self, path: &Path, turbofish: bool) -> Option<Path> {
+    self
+      .replace_path(path, turbofish)
+      .or_else(|| self.replace_imported_path(path, turbofish))
+  }
+
+  fn replace_imported_path(&self, path: &Path, turbofish: bool) -> Option<Path> {
+    let import = self.import(path)?;
+    let replaced = self.replace_path(&with_import(path, import), turbofish)?;
+
+    // the import itself is rewritten by `replace_use`, so the name may be already correct
+    let through_use = self.through_use(import);
+    let mut denoted = with_import(path, &through_use);
+    let mut expected = replaced.clone();
+    set_turbofish(&mut denoted, false);
+    set_turbofish(&mut expected, false);
+
+    match denoted == expected {
+      true => None,
+      false => Some(replaced),
+    }
+  }
+
+  /// `Mutex<u8>` + `use tokio::sync::Mutex` => `tokio::sync::Mutex<u8>`
+  pub fn imported_type(&self, ty: &Type) -> Option<Type> {
+    match ty {
+      Type::Path(TypePath { qself: None, path }) => Some(Type::Path(TypePath {
+        qself: None,
+        path: with_import(path, self.import(path)?),
+      })),
+      _ => None,
+    }
+  }
+
+  /// Keeps the imported name, if the replaced type is the one the rewritten import denotes
+  ///
+  /// `Sender<u8>` => `std::sync::mpsc::Sender<u8>` => `Sender<u8>`
+  pub fn keep_imported_name(&self, ty: &Type, replaced: Type) -> Type {
+    let (path, replaced_path) = match (ty, &replaced) {
+      (
+        Type::Path(TypePath { qself: None, path }), 
+        Type::Path(TypePath { qself: None, path: replaced_path })
+      ) => (path, replaced_path),
+      _ => return replaced,
+    };
+    let import = match self.import(path) {
+      Some(x) => x,
+      None => return replaced,
+    };
+
+    let denoted = with_import(path, &self.through_use(import));
+    if without_arguments(&denoted) != without_arguments(replaced_path) {
+      return replaced;
+    }
+
+    let mut result = path.clone();
+    if let (Some(x), Some(r)) = (result.segments.last_mut(), replaced_path.segments.last()) {
+      x.arguments = r.arguments.clone();
+    }
+
+    Type::Path(TypePath { qself: None, path: result })
+  }
+
+  /// The import as it is rewritten by `replace_use`
+  fn through_use(&self, import: &Path) -> Path {
+    self
+      .replace_path(import, false)
+      .or_else(|| self.pattern_path(import))
+      .unwrap_or_else(|| import.clone())
+  }
+
+  fn import(&self, path: &Path) -> Option<&Path> {
+    if path.leading_colon.is_some() {
+      return None;
+    }
+
+    let first = &path.segments.first()?.ident;
+    for scope in self.imports.iter().rev() {
+      if let Some(x) = scope.names.get(first) {
+        return Some(x);
+      }
+
+      if scope.is_module {
+        break;
+      }
+    }
+
+    None
+  }
+}
+
+/// `Client::connect` + `tokio_postgres::Client` => `tokio_postgres::Client::connect`
+fn with_import(path: &Path, import: &Path) -> Path {
+  let mut result = import.clone();
+  let first = &path.segments[0];
+  if let Some(last) = result.segments.last_mut() {
+    if !matches!(first.arguments, PathArguments::None) {
+      last.arguments = first.arguments.clone();
+    }
+  }
+
+  result.segments.extend(path.segments.iter().skip(1).cloned());
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use quote::ToTokens;
+  use syn::{parse_quote, Path};
+
+  use crate::SyncAFold;
+  use super::ImportScope;
+
+  #[test]
+  fn resolve_path() {
+    let mut fold = SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
+        (parse_quote!(tokio::sync::Mutex), parse_quote!(std::sync::RwLock)),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    fold.imports.push(ImportScope::module(&[
+      parse_quote!(use tokio_postgres::{self as pg, Client};),
+      parse_quote!(use tokio::sync::Mutex as Lock;),
+    ]));
+    fold.imports.push(ImportScope::block(&[parse_quote!(use tokio::sync;)]));
+
+    let resolve = |fold: &SyncAFold, path: Path| {
+      fold
+        .resolve_path(&path, true)
+        .map(|x| x.to_token_stream().to_string())
+    };
+
+    assert_eq!(resolve(&fold, parse_quote!(Client::connect)), None);
+    assert_eq!(resolve(&fold, parse_quote!(Lock::<u8>::new)), None);
+    assert_eq!(
+      resolve(&fold, parse_quote!(pg::Client::connect)),
+      Some("postgres :: Client :: connect".into())
+    );
+    assert_eq!(
+      resolve(&fold, parse_quote!(sync::Mutex::<u8>::new)),
+      Some("std :: sync :: RwLock :: < u8 > :: new".into())
+    );
+
+    fold.imports.push(ImportScope::module(&[]));
+    assert_eq!(resolve(&fold, parse_quote!(pg::Client)), None);
+  }
+}
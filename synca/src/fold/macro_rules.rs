@@ -0,0 +1,319 @@
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse::Parser, punctuated::Punctuated, Block, Expr, Item, Macro, Path, PathSegment, Stmt};
+
+use crate::{fold::expand::MacroRules, macros::MacroPolicy, SyncAFold};
+
+/// Depth of nested expansions of local macros, the same as the default `recursion_limit` of rustc
+const EXPAND_LIMIT: usize = 128;
+
+impl SyncAFold {
+  /// Folds transcribers of `macro_rules!`, matchers are kept as is
+  ///
+  /// In sync modules `.await` and `async` are removed, paths are replaced.
+  pub fn fold_macro_rules(&self, tokens: TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for i in 3..tokens.len() {
+      let is_rule = matches!(
+        (&tokens[i - 3], &tokens[i - 2], &tokens[i - 1]),
+        (TokenTree::Group(_), TokenTree::Punct(eq), TokenTree::Punct(gt))
+          if eq.as_char() == '=' && gt.as_char() == '>'
+      );
+
+      if let (true, TokenTree::Group(g)) = (is_rule, &tokens[i]) {
+        let mut group = Group::new(g.delimiter(), self.fold_transcriber(g.stream()));
+        group.set_span(g.span());
+        tokens[i] = TokenTree::Group(group);
+      }
+    }
+
+    tokens.into_iter().collect()
+  }
+
+  fn fold_transcriber(&self, tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut result: Vec<TokenTree> = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+      let next = tokens.get(i + 1);
+      match &tokens[i] {
+        // `$name`, `$crate`
+        TokenTree::Punct(p) if p.as_char() == '$' && matches!(next, Some(TokenTree::Ident(_))) => {
+          result.extend(tokens[i..i + 2].iter().cloned());
+          i += 2;
+          continue;
+        },
+        TokenTree::Punct(p) if self.lowers_async() && p.as_char() == '.'
+          && matches!(next, Some(TokenTree::Ident(x)) if x == "await") => {
+          i += 2;
+          continue;
+        },
+        TokenTree::Ident(x) if self.lowers_async() && x == "async" => {
+          // `async move { .. }` => `{ .. }`, `async move |x| ..` => `move |x| ..`
+          i += match (next, tokens.get(i + 2)) {
+            (Some(TokenTree::Ident(m)), Some(TokenTree::Group(_))) if m == "move" => 2,
+            _ => 1,
+          };
+          continue;
+        },
+        TokenTree::Ident(x) => {
+          if self.replaces() && is_path_start(&result) {
+            let (path, len) = token_path(&tokens[i..]);
+            if let Some(target) = self.resolve_path(&path, false) {
+              result.extend(target.into_token_stream().into_iter().map(|mut tt| {
+                tt.set_span(x.span());
+                tt
+              }));
+              i += len;
+              continue;
+            }
+          }
+
+          let len = self.external_path_len(&tokens[i..], &result);
+          if len > 0 {
+            result.extend(tokens[i..i + len].iter().cloned());
+            i += len;
+            continue;
+          }
+
+          result.push(TokenTree::Ident(self.rename_ident(x).unwrap_or_else(|| x.clone())));
+        },
+        TokenTree::Group(g) => {
+          let mut group = Group::new(g.delimiter(), self.fold_transcriber(g.stream()));
+          group.set_span(g.span());
+          result.push(TokenTree::Group(group));
+        },
+        tt => result.push(tt.clone()),
+      }
+
+      i += 1;
+    }
+
+    result.into_iter().collect()
+  }
+
+  /// Collects `macro_rules!` definitions of macros with the `expand` policy
+  pub fn collect_local_macros(&mut self, items: &[Item]) {
+    for item in items {
+      if let Item::Macro(x) = item {
+        match &x.ident {
+          Some(name) if x.mac.path.is_ident("macro_rules")
+            && self.macro_policy(&Path::from(name.clone())) == MacroPolicy::Expand =>
+            { self.local_macros.insert(name.clone(), x.clone()); },
+          _ => { },
+        }
+      }
+    }
+  }
+
+  pub fn expand_expr(&mut self, mac: &Macro) -> Option<Expr> {
+    let tokens = self.expand_macro(mac)?;
+    let result = syn::parse2::<Expr>(tokens.clone())
+      .or_else(|_| syn::parse2::<Block>(quote!({ #tokens })).map(|block| Expr::Block(syn::ExprBlock {
+        attrs: vec![],
+        label: None,
+        block,
+      })));
+
+    self.expansion_result(mac, result)
+  }
+
+  pub fn expand_items(&mut self, mac: &Macro) -> Option<Vec<Item>> {
+    let tokens = self.expand_macro(mac)?;
+    let result = syn::parse2::<syn::File>(tokens).map(|x| x.items);
+
+    self.expansion_result(mac, result)
+  }
+
+  pub fn expand_stmts(&mut self, mac: &Macro) -> Option<Vec<Stmt>> {
+    let tokens = self.expand_macro(mac)?;
+    let result = Block::parse_within.parse2(tokens);
+
+    self.expansion_result(mac, result)
+  }
+
+  /// Expansion of a synca macro or a local macro with the `expand` policy, 
+  /// `expand_expr`, `expand_items` and `expand_stmts` parse it in their position
+  fn expand_macro(&mut self, mac: &Macro) -> Option<TokenStream> {
+    if let Some(result) = self.synca_macro(mac) {
+      return self.expansion_result(mac, result);
+    }
+
+    let definition = self.local_macros.get(mac.path.get_ident()?)?;
+    if self.expand_depth >= EXPAND_LIMIT {
+      let name = &mac.path.segments[0].ident;
+      let message = format!("SyncA recursion limit reached while expanding `{}!`", name);
+      return self.expansion_result(mac, Err(syn::Error::new(name.span(), message)));
+    }
+
+    let result = MacroRules::parse(definition.mac.tokens.clone())
+      .and_then(|x| x.expand(mac.tokens.clone(), mac.path.segments[0].ident.span()));
+
+    self.expansion_result(mac, result)
+  }
+
+  /// Folds nodes of an expansion, nested expansions are limited by [`EXPAND_LIMIT`]
+  pub fn fold_expanded<T>(&mut self, fold: impl FnOnce(&mut Self) -> T) -> T {
+    self.expand_depth += 1;
+    let result = fold(self);
+    self.expand_depth -= 1;
+
+    result
+  }
+
+  fn expansion_result<T>(&mut self, mac: &Macro, result: syn::Result<T>) -> Option<T> {
+    match result {
+      Ok(x) => Some(x),
+      Err(e) => {
+        self.errors.push(syn::Error::new_spanned(mac, e.to_string()));
+        None
+      },
+    }
+  }
+}
+
+/// Paths start after anything but `.`, `:` and `$`
+pub fn is_path_start(result: &[TokenTree]) -> bool {
+  !matches!(result.last(), Some(TokenTree::Punct(p)) if matches!(p.as_char(), '.' | ':' | '$'))
+}
+
+/// `tokio::fs::read(..)` => (`tokio::fs::read`, 5)
+pub fn token_path(tokens: &[TokenTree]) -> (Path, usize) {
+  let mut segments = Punctuated::new();
+  let mut len = 0;
+
+  while let Some(TokenTree::Ident(x)) = tokens.get(len) {
+    segments.push(PathSegment::from(x.clone()));
+    len += 1;
+
+    match (tokens.get(len), tokens.get(len + 1), tokens.get(len + 2)) {
+      (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)), Some(TokenTree::Ident(_)))
+        if a.as_char() == ':' && b.as_char() == ':' => len += 2,
+      _ => break,
+    }
+  }
+
+  (Path { leading_colon: None, segments }, len)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use quote::{quote, ToTokens};
+  use syn::{parse_quote, ItemMod};
+
+  use crate::{macros::MacroPolicy, SyncAFold};
+
+  #[test]
+  fn fold_macro_rules() {
+    let fold = SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+
+    assert_eq!(
+      fold.fold_macro_rules(quote!(
+        ($c:expr) => { $c.query_one("SQL").await };
+        (fn $name:ident) => {
+          pub async fn $name(c: &tokio_postgres::Client) {
+            let f = async move { c.close().await };
+            $crate::wait(f).await
+          }
+        };
+      )).to_string(),
+      quote!(
+        ($c:expr) => { $c.query_one("SQL") };
+        (fn $name:ident) => {
+          pub fn $name(c: &postgres::Client) {
+            let f = { c.close() };
+            $crate::wait(f)
+          }
+        };
+      ).to_string()
+    );
+  }
+
+  #[test]
+  fn expand_local_macros() {
+    use syn::fold::Fold;
+
+    let mut fold = SyncAFold {
+      is_async: false,
+      macros: vec![(parse_quote!(query), MacroPolicy::Expand)],
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let expected: ItemMod = parse_quote!(
+      mod my_mod {
+        macro_rules! query {
+          ($c:expr) => { $c.query_one("SQL") };
+          (fn $name:ident) => { fn $name(c: &Client) -> Row { query!(c) } };
+        }
+
+        fn get(c: &Client) -> Row { c.query_one("SQL") }
+
+        fn first(c: &Client) -> Row {
+          let row = c.query_one("SQL");
+          row
+        }
+      }
+    );
+
+    assert_eq!(
+      fold.fold_item_mod(parse_quote!(
+        mod my_mod {
+          macro_rules! query {
+            ($c:expr) => { $c.query_one("SQL").await };
+            (fn $name:ident) => { async fn $name(c: &Client) -> Row { query!(c) } };
+          }
+
+          query!(fn get);
+
+          async fn first(c: &Client) -> Row {
+            let row = query!(c);
+            row
+          }
+        }
+      )).to_token_stream().to_string(),
+      expected.to_token_stream().to_string()
+    );
+  }
+
+  #[test]
+  fn expand_limit() {
+    use syn::fold::Fold;
+
+    // rustc runs proc macros with 8 MiB of stack, test threads have 2 MiB
+    let errors = std::thread::Builder::new()
+      .stack_size(8 * 1024 * 1024)
+      .spawn(|| {
+        let mut fold = SyncAFold {
+          is_async: false,
+          macros: vec![(parse_quote!(again), MacroPolicy::Expand)],
+          ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+        };
+
+        fold.fold_item_mod(parse_quote!(
+          mod my_mod {
+            macro_rules! again {
+              ($x:expr) => { again!($x) };
+            }
+
+            fn get() -> i32 { again!(1) }
+          }
+        ));
+        assert_eq!(fold.expand_depth, 0);
+
+        fold.errors.to_compile_error().to_string()
+      })
+      .unwrap()
+      .join()
+      .unwrap();
+
+    assert!(errors.contains("recursion limit reached while expanding `again!`"));
+  }
+}
@@ -0,0 +1,457 @@
+use std::cmp::Reverse;
+
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+  fold::Fold, parse::{ParseStream, Parser}, punctuated::Punctuated, Expr, Item, ItemStatic, Macro, Pat, Path, Token
+};
+
+use crate::{fold::{cfg::Cfg, tokens::strip_await}, macros::MacroPolicy, SyncAFold};
+
+/// Policies of macros without `macros!` rules, matched by the macro name
+const DEFAULT_MACROS: &[(&str, MacroPolicy)] = &[
+  ("assert", MacroPolicy::Exprs),
+  ("assert_eq", MacroPolicy::Exprs),
+  ("assert_ne", MacroPolicy::Exprs),
+  ("debug_assert", MacroPolicy::Exprs),
+  ("debug_assert_eq", MacroPolicy::Exprs),
+  ("debug_assert_ne", MacroPolicy::Exprs),
+  ("print", MacroPolicy::Exprs),
+  ("println", MacroPolicy::Exprs),
+  ("eprint", MacroPolicy::Exprs),
+  ("eprintln", MacroPolicy::Exprs),
+  ("format", MacroPolicy::Exprs),
+  ("format_args", MacroPolicy::Exprs),
+  ("write", MacroPolicy::Exprs),
+  ("writeln", MacroPolicy::Exprs),
+  ("panic", MacroPolicy::Exprs),
+  ("todo", MacroPolicy::Exprs),
+  ("unimplemented", MacroPolicy::Exprs),
+  ("unreachable", MacroPolicy::Exprs),
+  ("dbg", MacroPolicy::Exprs),
+  ("vec", MacroPolicy::Exprs),
+  ("matches", MacroPolicy::Exprs),
+  ("cfg_if", MacroPolicy::Items),
+  ("thread_local", MacroPolicy::Items),
+  ("lazy_static", MacroPolicy::Items),
+  ("pin_project", MacroPolicy::Items),
+  ("stringify", MacroPolicy::Verbatim),
+  ("concat", MacroPolicy::Verbatim),
+  ("quote", MacroPolicy::Verbatim),
+  ("quote_spanned", MacroPolicy::Verbatim),
+  ("parse_quote", MacroPolicy::Verbatim),
+  ("format_ident", MacroPolicy::Verbatim),
+];
+
+impl SyncAFold {
+  /// Policy of the macro: `macros!` rules, then the default table
+  ///
+  /// Rules are matched by path suffix, `sqlx::query => verbatim` matches 
+  /// `sqlx::query!` and `query!`. The exact match wins, then the longest rule, 
+  /// then the first declared.
+  pub fn macro_policy(&self, path: &Path) -> MacroPolicy {
+    let rule = self
+      .macros
+      .iter()
+      .filter(|(rule, _)| is_suffix_match(rule, path))
+      .min_by_key(|(rule, _)| (rule.segments.len() != path.segments.len(), Reverse(rule.segments.len())));
+    if let Some((_, policy)) = rule {
+      return *policy;
+    }
+
+    let name = match path.segments.last() {
+      Some(x) => x.ident.to_string(),
+      None => return MacroPolicy::Tokens,
+    };
+    DEFAULT_MACROS
+      .iter()
+      .find(|(x, _)| *x == name)
+      .map(|(_, policy)| *policy)
+      .unwrap_or(MacroPolicy::Tokens)
+  }
+
+  /// Arguments of the macro processed according to its policy
+  ///
+  /// Arguments that can not be parsed are processed as tokens.
+  pub fn fold_macro_tokens(&mut self, mac: &Macro) -> TokenStream {
+    let tokens = mac.tokens.clone();
+    if mac.path.is_ident("macro_rules") {
+      return self.fold_macro_rules(tokens);
+    }
+
+    let folded = match self.macro_policy(&mac.path) {
+      MacroPolicy::Verbatim => return tokens,
+      MacroPolicy::Tokens | MacroPolicy::Expand => None,
+      MacroPolicy::Exprs => match mac.path.segments.last() {
+        Some(x) if x.ident == "matches" => self.fold_matches(tokens.clone()),
+        Some(x) if x.ident == "vec" => self
+          .fold_exprs(tokens.clone())
+          .or_else(|| self.fold_repeat(tokens.clone())),
+        _ => self.fold_exprs(tokens.clone()),
+      },
+      MacroPolicy::Items => self.fold_items(tokens.clone()),
+    };
+
+    folded.unwrap_or_else(|| {
+      let tokens = self.context_tokens(self.rename_tokens(tokens));
+      match self.lowers_async() {
+        true => strip_await(tokens),
+        false => tokens,
+      }
+    })
+  }
+
+  /// Items of the macro (`my_crate::define_api! { .. }`)
+  ///
+  /// `lazy_static!` items (`static ref`) are parsed as statics, 
+  /// if arguments are not items, items of braced groups are folded (`cfg_if!`).
+  fn fold_items(&mut self, tokens: TokenStream) -> Option<TokenStream> {
+    let parser = |input: ParseStream| -> syn::Result<Vec<Item>> {
+      let mut items = vec![];
+      while !input.is_empty() {
+        items.push(input.parse()?);
+      }
+
+      Ok(items)
+    };
+    let (statics, static_refs) = without_static_ref(tokens.clone());
+    let items = match parser.parse2(statics) {
+      Ok(x) => x,
+      Err(_) => return self.fold_item_groups(tokens),
+    };
+    let mut static_refs = static_refs.into_iter();
+    let items: Vec<TokenStream> = items
+      .into_iter()
+      .map(|x| {
+        let is_static_ref = matches!(x, Item::Static(_)) && static_refs.next() == Some(true);
+        match (self.fold_item(x), is_static_ref) {
+          (Item::Static(x), true) => with_static_ref(&x),
+          (x, _) => x.into_token_stream(),
+        }
+      })
+      .collect();
+
+    Some(quote!(#(#items)*))
+  }
+
+  /// `if #[cfg(feature = "a")] { items } else { items }`
+  fn fold_item_groups(&mut self, tokens: TokenStream) -> Option<TokenStream> {
+    let mut is_folded = false;
+    let result = tokens
+      .into_iter()
+      .map(|tt| match tt {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => match self.fold_items(g.stream()) {
+          Some(x) => {
+            is_folded = true;
+            let mut group = Group::new(Delimiter::Brace, x);
+            group.set_span(g.span());
+            TokenTree::Group(group)
+          },
+          None => TokenTree::Group(g),
+        },
+        tt => tt,
+      })
+      .collect();
+
+    is_folded.then_some(result)
+  }
+
+  /// `a, b, c`
+  fn fold_exprs(&mut self, tokens: TokenStream) -> Option<TokenStream> {
+    let mut exprs = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(tokens).ok()?;
+    exprs.retain_cfg(self);
+    let exprs: Punctuated<Expr, Token![,]> = exprs
+      .into_pairs()
+      .map(|x| {
+        let (expr, comma) = x.into_tuple();
+        syn::punctuated::Pair::new(self.fold_expr(expr), comma)
+      })
+      .collect();
+
+    Some(exprs.into_token_stream())
+  }
+
+  /// `vec![value; len]`
+  fn fold_repeat(&mut self, tokens: TokenStream) -> Option<TokenStream> {
+    let parser = |input: ParseStream| -> syn::Result<(Expr, Token![;], Expr)> {
+      Ok((input.parse()?, input.parse()?, input.parse()?))
+    };
+    let (value, semi, len) = parser.parse2(tokens).ok()?;
+    let (value, len) = (self.fold_expr(value), self.fold_expr(len));
+
+    Some(quote!(#value #semi #len))
+  }
+
+  /// `matches!(expr, pattern if guard)`
+  fn fold_matches(&mut self, tokens: TokenStream) -> Option<TokenStream> {
+    let args: MatchesArgs = syn::parse2(tokens).ok()?;
+    let expr = self.fold_expr(args.expr);
+    let pat = self.fold_pat(args.pat);
+    let guard = args.guard.map(|(if_token, x)| {
+      let x = self.fold_expr(x);
+      quote!(#if_token #x)
+    });
+    let (comma, trailing) = (args.comma, args.trailing);
+
+    Some(quote!(#expr #comma #pat #guard #trailing))
+  }
+}
+
+struct MatchesArgs {
+  expr: Expr,
+  comma: Token![,],
+  pat: Pat,
+  guard: Option<(Token![if], Expr)>,
+  trailing: Option<Token![,]>,
+}
+
+/// `static ref X: T = ..;` => `static X: T = ..;`
+///
+/// Returns a flag for every `static` item of the macro: `ref` was removed.
+fn without_static_ref(tokens: TokenStream) -> (TokenStream, Vec<bool>) {
+  let mut static_refs = vec![];
+  let mut result: Vec<TokenTree> = vec![];
+  for tt in tokens {
+    match (&tt, result.last()) {
+      (TokenTree::Ident(x), Some(TokenTree::Ident(prev))) if x == "ref" && prev == "static" => {
+        if let Some(last) = static_refs.last_mut() {
+          *last = true;
+        }
+        continue;
+      },
+      // `'static` is a lifetime, not an item
+      (TokenTree::Ident(x), prev) if x == "static"
+        && !matches!(prev, Some(TokenTree::Punct(p)) if p.as_char() == '\'') => static_refs.push(false),
+      _ => { },
+    }
+
+    result.push(tt);
+  }
+
+  (result.into_iter().collect(), static_refs)
+}
+
+/// `static X: T = ..;` => `static ref X: T = ..;`
+fn with_static_ref(item: &ItemStatic) -> TokenStream {
+  let ItemStatic { attrs, vis, static_token, mutability, ident, colon_token, ty, eq_token, expr, semi_token } = item;
+  let ref_token = Ident::new("ref", static_token.span);
+
+  quote!(#(#attrs)* #vis #static_token #ref_token #mutability #ident #colon_token #ty #eq_token #expr #semi_token)
+}
+
+/// `sqlx::query` + `query`, `::sqlx::query` + `sqlx::query`
+fn is_suffix_match(rule: &Path, path: &Path) -> bool {
+  rule
+    .segments
+    .iter()
+    .rev()
+    .zip(path.segments.iter().rev())
+    .all(|(r, p)| r.ident == p.ident)
+}
+
+impl syn::parse::Parse for MatchesArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    Ok(MatchesArgs {
+      expr: input.parse()?,
+      comma: input.parse()?,
+      pat: Pat::parse_multi_with_leading_vert(input)?,
+      guard: match input.peek(Token![if]) {
+        true => Some((input.parse()?, input.parse()?)),
+        false => None,
+      },
+      trailing: input.parse()?,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use quote::ToTokens;
+  use syn::{fold::Fold, parse_quote, Macro, Path};
+
+  use crate::{macros::MacroPolicy, SyncAFold};
+
+  #[test]
+  fn macro_policy() {
+    let fold = SyncAFold {
+      macros: vec![
+        (parse_quote!(sqlx::query), MacroPolicy::Verbatim),
+        (parse_quote!(sqlx::query::inner), MacroPolicy::Items),
+        (parse_quote!(println), MacroPolicy::Tokens),
+        (parse_quote!(a::select), MacroPolicy::Verbatim),
+        (parse_quote!(b::select), MacroPolicy::Items),
+        (parse_quote!(fetch), MacroPolicy::Exprs),
+        (parse_quote!(sqlx::fetch), MacroPolicy::Verbatim),
+      ],
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let policy = |path: Path| fold.macro_policy(&path);
+
+    assert_eq!(policy(parse_quote!(sqlx::query)), MacroPolicy::Verbatim);
+    assert_eq!(policy(parse_quote!(query)), MacroPolicy::Verbatim);
+    assert_eq!(policy(parse_quote!(::sqlx::query)), MacroPolicy::Verbatim);
+    assert_eq!(policy(parse_quote!(other::query)), MacroPolicy::Tokens);
+    assert_eq!(policy(parse_quote!(inner)), MacroPolicy::Items);
+    assert_eq!(policy(parse_quote!(std::println)), MacroPolicy::Tokens);
+    assert_eq!(policy(parse_quote!(std::assert_eq)), MacroPolicy::Exprs);
+    assert_eq!(policy(parse_quote!(quote::quote)), MacroPolicy::Verbatim);
+    assert_eq!(policy(parse_quote!(select)), MacroPolicy::Verbatim);
+    assert_eq!(policy(parse_quote!(a::select)), MacroPolicy::Verbatim);
+    assert_eq!(policy(parse_quote!(b::select)), MacroPolicy::Items);
+    assert_eq!(policy(parse_quote!(fetch)), MacroPolicy::Exprs);
+    assert_eq!(policy(parse_quote!(sqlx::fetch)), MacroPolicy::Verbatim);
+  }
+
+  #[test]
+  fn fold_item_macros() {
+    let mut fold = SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio::sync::Mutex), parse_quote!(std::sync::Mutex)),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let mut folded = |mac: Macro| fold.fold_macro(mac).to_token_stream().to_string();
+    let expected = |mac: Macro| mac.to_token_stream().to_string();
+
+    assert_eq!(
+      folded(parse_quote!(cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+          async fn a() -> tokio::sync::Mutex<u8> { b().await }
+        } else {
+          async fn a() { }
+        }
+      })),
+      expected(parse_quote!(cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+          fn a() -> std::sync::Mutex<u8> { b() }
+        } else {
+          fn a() { }
+        }
+      }))
+    );
+    assert_eq!(
+      folded(parse_quote!(thread_local! {
+        static STATE: tokio::sync::Mutex<u8> = tokio::sync::Mutex::new(0);
+      })),
+      expected(parse_quote!(thread_local! {
+        static STATE: std::sync::Mutex<u8> = std::sync::Mutex::new(0);
+      }))
+    );
+    assert_eq!(
+      folded(parse_quote!(lazy_static! {
+        pub static ref STATE: tokio::sync::Mutex<u8> = tokio::sync::Mutex::new(0);
+      })),
+      expected(parse_quote!(lazy_static! {
+        pub static ref STATE: std::sync::Mutex<u8> = std::sync::Mutex::new(0);
+      }))
+    );
+    assert_eq!(
+      folded(parse_quote!(lazy_static! {
+        #[allow(dead_code)]
+        static ref NAME: &'static str = { let x: &'static str = "x"; x };
+        static ref STATE: tokio::sync::Mutex<&'static str> = tokio::sync::Mutex::new("x");
+      })),
+      expected(parse_quote!(lazy_static! {
+        #[allow(dead_code)]
+        static ref NAME: &'static str = { let x: &'static str = "x"; x };
+        static ref STATE: std::sync::Mutex<&'static str> = std::sync::Mutex::new("x");
+      }))
+    );
+    assert_eq!(
+      folded(parse_quote!(lazy_static! {
+        static ref STATE: tokio::sync::Mutex<u8> = tokio::sync::Mutex::new(0);
+        static NAME: &'static str = "x";
+        static ref OTHER: tokio::sync::Mutex<u8> = tokio::sync::Mutex::new(1);
+      })),
+      expected(parse_quote!(lazy_static! {
+        static ref STATE: std::sync::Mutex<u8> = std::sync::Mutex::new(0);
+        static NAME: &'static str = "x";
+        static ref OTHER: std::sync::Mutex<u8> = std::sync::Mutex::new(1);
+      }))
+    );
+    assert_eq!(
+      folded(parse_quote!(pin_project_lite::pin_project! {
+        pub struct Guarded<T> {
+          #[pin]
+          inner: T,
+          lock: tokio::sync::Mutex<()>,
+        }
+      })),
+      expected(parse_quote!(pin_project_lite::pin_project! {
+        pub struct Guarded<T> {
+          #[pin]
+          inner: T,
+          lock: std::sync::Mutex<()>,
+        }
+      }))
+    );
+  }
+
+  #[test]
+  fn fold_macro_args() {
+    let fold = || SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
+        (parse_quote!(tokio_postgres::error::SqlState), parse_quote!(postgres::error::SqlState)),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let folded = |mac: Macro| fold().fold_macro(mac).to_token_stream().to_string();
+    let expected = |mac: Macro| mac.to_token_stream().to_string();
+
+    assert_eq!(
+      folded(parse_quote!(assert_eq!(async { tokio_postgres::Client::connect().await }, 42,))),
+      expected(parse_quote!(assert_eq!({ postgres::Client::connect() }, 42,)))
+    );
+    assert_eq!(
+      folded(parse_quote!(println!("{}", tokio_postgres::error::SqlState::UNIQUE_VIOLATION))),
+      expected(parse_quote!(println!("{}", postgres::error::SqlState::UNIQUE_VIOLATION)))
+    );
+    assert_eq!(
+      folded(parse_quote!(vec![tokio_postgres::Client::new(); n.await])),
+      expected(parse_quote!(vec![postgres::Client::new(); n]))
+    );
+    assert_eq!(
+      folded(parse_quote!(matches!(
+        code.await, 
+        &tokio_postgres::error::SqlState::UNIQUE_VIOLATION if check().await
+      ))),
+      expected(parse_quote!(matches!(
+        code, 
+        &postgres::error::SqlState::UNIQUE_VIOLATION if check()
+      )))
+    );
+    assert_eq!(
+      folded(parse_quote!(custom!(tokio_postgres::Client, x.await))),
+      expected(parse_quote!(custom!(tokio_postgres::Client, x)))
+    );
+    assert_eq!(
+      folded(parse_quote!(quote!(x.await))),
+      expected(parse_quote!(quote!(x.await)))
+    );
+
+    let mut fold = SyncAFold {
+      macros: vec![
+        (parse_quote!(my_crate::define_api), MacroPolicy::Items),
+        (parse_quote!(serde_json::json), MacroPolicy::Exprs),
+      ],
+      ..fold()
+    };
+    assert_eq!(
+      fold.fold_macro(parse_quote!(define_api! { async fn get() -> tokio_postgres::Client { } }))
+        .to_token_stream()
+        .to_string(),
+      expected(parse_quote!(define_api! { fn get() -> postgres::Client { } }))
+    );
+    assert_eq!(
+      fold.fold_macro(parse_quote!(json!({ "a": x.await })))
+        .to_token_stream()
+        .to_string(),
+      expected(parse_quote!(json!({ "a": x })))
+    );
+  }
+}
@@ -1,5 +1,24 @@
 mod attrs;
+mod cfg;
+mod context;
+mod expand;
+#[allow(clippy::module_inception)]
 mod fold;
+mod futures;
+mod imports;
+mod macro_rules;
+mod macros;
+mod paths;
+mod patterns;
+mod rename;
+mod scope;
+mod select;
+mod tokens;
+mod uses;
 
 pub use attrs::*;
-pub use fold::*;
\ No newline at end of file
+pub use fold::*;
+pub use imports::ImportScope;
+pub use scope::ScopedRules;
+pub use select::FnBody;
+pub use uses::is_empty_use;
\ No newline at end of file
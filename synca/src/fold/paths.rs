@@ -0,0 +1,174 @@
+use syn::{Path, PathArguments, QSelf, Type, TypePath};
+
+use crate::{fold::patterns::substitute, SyncAFold};
+
+impl SyncAFold {
+  /// Replaces the longest prefix of the path, that is described in `replace!`
+  ///
+  /// `tokio_postgres::Client::connect` => `postgres::Client::connect`
+  ///
+  /// Wildcard rules (`tokio_postgres::* => postgres::*`) match only
+  /// paths that are longer than the rule.
+  ///
+  /// Generic arguments of the replaced segment are kept, `turbofish`
+  /// must be true for paths in expression position.
+  pub fn replace_path(&self, path: &Path, turbofish: bool) -> Option<Path> {
+    self.replace_prefix(path, turbofish, false)
+  }
+
+  /// Same as `replace_path`, `is_glob` marks the path as a prefix (`use tokio_postgres::*`)
+  pub fn replace_prefix(&self, path: &Path, turbofish: bool, is_glob: bool) -> Option<Path> {
+    for len in (1..=path.segments.len()).rev() {
+      let mut key = Path {
+        leading_colon: path.leading_colon,
+        segments: path.segments.iter().take(len).cloned().collect(),
+      };
+      set_turbofish(&mut key, false);
+
+      let is_prefix = is_glob || len < path.segments.len();
+      let mut target = match self.path_target(&key, is_prefix) {
+        Some(x) => x,
+        None => {
+          let last = key.segments.last_mut()?;
+          if last.arguments.is_empty() {
+            continue;
+          }
+
+          let arguments = std::mem::replace(&mut last.arguments, PathArguments::None);
+          match self.path_target(&key, is_prefix) {
+            Some(mut x) => {
+              let target_last = x.segments.last_mut()?;
+              if target_last.arguments.is_empty() {
+                target_last.arguments = arguments;
+              }
+
+              x
+            },
+            None => continue,
+          }
+        }
+      };
+
+      set_turbofish(&mut target, turbofish);
+      target.segments.extend(path.segments.iter().skip(len).cloned());
+
+      return Some(target);
+    }
+
+    None
+  }
+
+  /// Replaces the trait of the qualified path
+  ///
+  /// `<T as tokio::io::AsyncRead>::poll_read` => `<T as std::io::Read>::poll_read`
+  pub fn resolve_qualified_path(&self, qself: &QSelf, path: &Path) -> Option<(QSelf, Path)> {
+    if qself.position == 0 {
+      return None;
+    }
+
+    let trait_path = Path {
+      leading_colon: path.leading_colon,
+      segments: path.segments.iter().take(qself.position).cloned().collect(),
+    };
+    let mut new_path = self.resolve_path(&trait_path, false)?;
+    let mut new_qself = qself.clone();
+    new_qself.position = new_path.segments.len();
+    new_path.segments.extend(path.segments.iter().skip(qself.position).cloned());
+
+    Some((new_qself, new_path))
+  }
+
+  fn path_target(&self, key: &Path, is_prefix: bool) -> Option<Path> {
+    let ty = Type::Path(TypePath { qself: None, path: key.clone() });
+
+    match self.types.get(&ty) {
+      Some(Type::Path(TypePath { qself: None, path })) => return Some(path.clone()),
+      Some(_) => return None,
+      None => { },
+    }
+
+    if let Some((target, bindings)) = self.match_pattern(&ty) {
+      if let Type::Path(TypePath { qself: None, path }) = substitute(&target, &bindings) {
+        return Some(path);
+      }
+    }
+
+    // `tokio::sync::Mutex::new` - arguments are inferred
+    if key.segments.iter().all(|x| x.arguments.is_empty()) {
+      if let Some(x) = self.pattern_path(key) {
+        return Some(x);
+      }
+    }
+
+    match is_prefix {
+      true => self.paths.get(key).cloned(),
+      false => None,
+    }
+  }
+}
+
+pub fn set_turbofish(path: &mut Path, turbofish: bool) {
+  for segment in path.segments.iter_mut() {
+    if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+      args.colon2_token = if turbofish { Some(Default::default()) } else { None };
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use quote::ToTokens;
+  use syn::{parse_quote, Path};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn replace_path() {
+    let fold = SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
+        (parse_quote!(tokio::sync::Mutex), parse_quote!(std::sync::Mutex)),
+        (parse_quote!(tokio::fs), parse_quote!(std::fs)),
+      ]),
+      paths: HashMap::from([
+        (parse_quote!(tokio_postgres), parse_quote!(postgres)),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let replace = |path: Path, turbofish| {
+      fold
+        .replace_path(&path, turbofish)
+        .map(|x| x.to_token_stream().to_string())
+    };
+
+    assert_eq!(replace(parse_quote!(tokio::Row), true), None);
+    assert_eq!(replace(parse_quote!(tokio_postgres), false), None);
+    assert_eq!(
+      replace(parse_quote!(tokio_postgres::Row), false),
+      Some("postgres :: Row".into())
+    );
+    assert_eq!(
+      replace(parse_quote!(tokio::fs::read), true),
+      Some("std :: fs :: read".into())
+    );
+    assert_eq!(
+      replace(parse_quote!(tokio_postgres::Client), false),
+      Some("postgres :: Client".into())
+    );
+    assert_eq!(
+      replace(parse_quote!(tokio_postgres::Client::connect), true),
+      Some("postgres :: Client :: connect".into())
+    );
+    assert_eq!(
+      replace(parse_quote!(tokio::sync::Mutex::<u8>::new), true),
+      Some("std :: sync :: Mutex :: < u8 > :: new".into())
+    );
+    assert_eq!(
+      replace(parse_quote!(tokio::sync::Mutex<u8>), false),
+      Some("std :: sync :: Mutex < u8 >".into())
+    );
+  }
+}
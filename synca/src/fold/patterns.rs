@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use syn::{
+  fold::{self, Fold},
+  GenericArgument, Ident, Path, PathArguments, ReturnType, Type, TypeParamBound, TypePath
+};
+
+use crate::{replace::is_placeholder, SyncAFold};
+
+impl SyncAFold {
+  /// Finds the first pattern (`tokio::sync::Mutex<$T>`) that matches the type
+  ///
+  /// Returns the target of the pattern and the bound placeholders.
+  pub fn match_pattern(&self, ty: &Type) -> Option<(Type, HashMap<Ident, Type>)> {
+    for (pattern, target) in self.type_patterns.iter() {
+      let mut bindings = HashMap::new();
+      if match_type(pattern, ty, &mut bindings) {
+        return Some((target.clone(), bindings));
+      }
+    }
+
+    None
+  }
+
+  /// Path of the pattern target for the pattern path without arguments
+  ///
+  /// `tokio::sync::Mutex` + `tokio::sync::Mutex<$T> => std::sync::Mutex<$T>` => `std::sync::Mutex`
+  pub fn pattern_path(&self, path: &Path) -> Option<Path> {
+    let path = without_arguments(path);
+
+    self.type_patterns.iter().find_map(|x| match x {
+      (
+        Type::Path(TypePath { qself: None, path: pattern }), 
+        Type::Path(TypePath { qself: None, path: target })
+      ) if without_arguments(pattern) == path => Some(without_arguments(target)),
+      _ => None,
+    })
+  }
+}
+
+pub fn without_arguments(path: &Path) -> Path {
+  let mut result = path.clone();
+  for segment in result.segments.iter_mut() {
+    segment.arguments = PathArguments::None;
+  }
+
+  result
+}
+
+/// Replaces placeholders of the pattern target with bound types
+pub fn substitute(target: &Type, bindings: &HashMap<Ident, Type>) -> Type {
+  Substitute { bindings }.fold_type(target.clone())
+}
+
+struct Substitute<'a> {
+  bindings: &'a HashMap<Ident, Type>,
+}
+
+impl<'a> Fold for Substitute<'a> {
+  fn fold_type(&mut self, ty: Type) -> Type {
+    match placeholder(&ty).and_then(|x| self.bindings.get(x)) {
+      Some(x) => x.clone(),
+      None => fold::fold_type(self, ty),
+    }
+  }
+}
+
+fn placeholder(ty: &Type) -> Option<&Ident> {
+  match ty {
+    Type::Path(TypePath { qself: None, path }) => path
+      .get_ident()
+      .filter(|x| is_placeholder(x)),
+    _ => None,
+  }
+}
+
+fn match_type(pattern: &Type, ty: &Type, bindings: &mut HashMap<Ident, Type>) -> bool {
+  if let Some(name) = placeholder(pattern) {
+    return match bindings.get(name) {
+      Some(x) => x == ty,
+      None => {
+        bindings.insert(name.clone(), ty.clone());
+        true
+      },
+    };
+  }
+
+  match (pattern, ty) {
+    (_, Type::Group(t)) => match_type(pattern, &t.elem, bindings),
+    (Type::Paren(p), Type::Paren(t)) => match_type(&p.elem, &t.elem, bindings),
+    (Type::Path(p), Type::Path(t)) => {
+      match (&p.qself, &t.qself) {
+        (None, None) => { },
+        (Some(pq), Some(tq)) if pq.position == tq.position =>
+          if !match_type(&pq.ty, &tq.ty, bindings) {
+            return false;
+          },
+        _ => return false,
+      }
+
+      match_path(&p.path, &t.path, bindings)
+    },
+    (Type::Reference(p), Type::Reference(t)) =>
+      p.lifetime == t.lifetime
+        && p.mutability.is_some() == t.mutability.is_some()
+        && match_type(&p.elem, &t.elem, bindings),
+    (Type::Ptr(p), Type::Ptr(t)) =>
+      p.mutability.is_some() == t.mutability.is_some()
+        && match_type(&p.elem, &t.elem, bindings),
+    (Type::Slice(p), Type::Slice(t)) => match_type(&p.elem, &t.elem, bindings),
+    (Type::Array(p), Type::Array(t)) => p.len == t.len && match_type(&p.elem, &t.elem, bindings),
+    (Type::Tuple(p), Type::Tuple(t)) =>
+      p.elems.len() == t.elems.len()
+        && p.elems.iter().zip(t.elems.iter()).all(|(p, t)| match_type(p, t, bindings)),
+    (Type::TraitObject(p), Type::TraitObject(t)) => match_bounds(&p.bounds, &t.bounds, bindings),
+    (Type::ImplTrait(p), Type::ImplTrait(t)) => match_bounds(&p.bounds, &t.bounds, bindings),
+    _ => pattern == ty,
+  }
+}
+
+fn match_bounds<'a, I: IntoIterator<Item = &'a TypeParamBound> + Clone>(
+  pattern: I,
+  bounds: I,
+  bindings: &mut HashMap<Ident, Type>
+) -> bool {
+  pattern.clone().into_iter().count() == bounds.clone().into_iter().count()
+    && pattern.into_iter().zip(bounds).all(|x| match x {
+      (TypeParamBound::Trait(p), TypeParamBound::Trait(t)) =>
+        p.modifier == t.modifier && match_path(&p.path, &t.path, bindings),
+      (p, t) => p == t,
+    })
+}
+
+pub fn match_path(pattern: &Path, path: &Path, bindings: &mut HashMap<Ident, Type>) -> bool {
+  if pattern.leading_colon.is_some() != path.leading_colon.is_some()
+    || pattern.segments.len() != path.segments.len() {
+    return false;
+  }
+
+  pattern.segments.iter().zip(path.segments.iter()).all(|(p, t)| {
+    p.ident == t.ident && match (&p.arguments, &t.arguments) {
+      (PathArguments::None, PathArguments::None) => true,
+      (PathArguments::AngleBracketed(p), PathArguments::AngleBracketed(t)) =>
+        p.args.len() == t.args.len()
+          && p.args.iter().zip(t.args.iter()).all(|x| match x {
+            (GenericArgument::Type(p), GenericArgument::Type(t)) => match_type(p, t, bindings),
+            (GenericArgument::AssocType(p), GenericArgument::AssocType(t)) =>
+              p.ident == t.ident && p.generics == t.generics && match_type(&p.ty, &t.ty, bindings),
+            (p, t) => p == t,
+          }),
+      (PathArguments::Parenthesized(p), PathArguments::Parenthesized(t)) =>
+        p.inputs.len() == t.inputs.len()
+          && p.inputs.iter().zip(t.inputs.iter()).all(|(p, t)| match_type(p, t, bindings))
+          && match (&p.output, &t.output) {
+            (ReturnType::Default, ReturnType::Default) => true,
+            (ReturnType::Type(_, p), ReturnType::Type(_, t)) => match_type(p, t, bindings),
+            _ => false,
+          },
+      _ => false,
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::ToTokens;
+  use syn::{parse_quote, Type};
+
+  use crate::SyncAFold;
+  use super::substitute;
+
+  #[test]
+  fn match_pattern() {
+    let fold = SyncAFold {
+      is_async: false,
+      type_patterns: vec![
+        (parse_quote!(tokio::sync::Mutex<__synca_T>), parse_quote!(std::sync::Mutex<__synca_T>)),
+        (parse_quote!(Pair<__synca_T, __synca_T>), parse_quote!([__synca_T; 2])),
+      ],
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let replace = |ty: Type| {
+      fold
+        .match_pattern(&ty)
+        .map(|(target, bindings)| substitute(&target, &bindings).to_token_stream().to_string())
+    };
+
+    assert_eq!(replace(parse_quote!(tokio::sync::Mutex)), None);
+    assert_eq!(replace(parse_quote!(tokio::sync::RwLock<u8>)), None);
+    assert_eq!(
+      replace(parse_quote!(tokio::sync::Mutex<Vec<u8>>)),
+      Some("std :: sync :: Mutex < Vec < u8 > >".into())
+    );
+    assert_eq!(replace(parse_quote!(Pair<u8, u8>)), Some("[u8 ; 2]".into()));
+    assert_eq!(replace(parse_quote!(Pair<u8, u16>)), None);
+  }
+}
@@ -0,0 +1,318 @@
+use proc_macro2::{Group, TokenStream, TokenTree};
+use syn::{fold, Fields, Generics, GenericParam, Ident, ImplItem, Item, Path, Stmt, TraitItem, UseTree};
+
+use crate::{fold::macro_rules::{is_path_start, token_path}, SyncAFold};
+
+impl SyncAFold {
+  /// Renames the identifier according to `rename!`
+  ///
+  /// `connect_async` + `*_async => *` => `connect`. Only names defined in the template are renamed.
+  pub fn rename_ident(&self, ident: &Ident) -> Option<Ident> {
+    if !self.definitions.contains(ident) {
+      return None;
+    }
+
+    if let Some(x) = self.idents.get(ident) {
+      let mut result = x.clone();
+      result.set_span(ident.span());
+      return Some(result);
+    }
+
+    if self.ident_patterns.is_empty() {
+      return None;
+    }
+
+    let name = ident.to_string();
+    self.ident_patterns.iter().find_map(|(pattern, target)| {
+      let (prefix, suffix) = pattern.split_once('*')?;
+      let matched = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+      if matched.is_empty() {
+        return None;
+      }
+
+      // keywords and names starting with a digit are not renamed
+      let mut result = syn::parse_str::<Ident>(&target.replacen('*', matched, 1)).ok()?;
+      result.set_span(ident.span());
+      Some(result)
+    })
+  }
+
+  /// Renames identifiers in macro arguments
+  pub fn rename_tokens(&self, tokens: TokenStream) -> TokenStream {
+    if self.idents.is_empty() && self.ident_patterns.is_empty() {
+      return tokens;
+    }
+
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut result: Vec<TokenTree> = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+      let len = self.external_path_len(&tokens[i..], &result);
+      if len > 0 {
+        result.extend(tokens[i..i + len].iter().cloned());
+        i += len;
+        continue;
+      }
+
+      result.push(match &tokens[i] {
+        TokenTree::Ident(x) => TokenTree::Ident(self.rename_ident(x).unwrap_or_else(|| x.clone())),
+        TokenTree::Group(g) => {
+          let mut group = Group::new(g.delimiter(), self.rename_tokens(g.stream()));
+          group.set_span(g.span());
+          TokenTree::Group(group)
+        },
+        tt => tt.clone(),
+      });
+      i += 1;
+    }
+
+    result.into_iter().collect()
+  }
+
+  /// Number of tokens of the path of another crate at the start of `tokens`, 0 for other tokens
+  ///
+  /// `result` is the already folded tokens before the path.
+  pub fn external_path_len(&self, tokens: &[TokenTree], result: &[TokenTree]) -> usize {
+    let first = match tokens.first() {
+      Some(TokenTree::Ident(x)) => x,
+      _ => return 0,
+    };
+
+    let (path, len) = token_path(tokens);
+    let is_leading_colon = matches!(
+      result,
+      [.., TokenTree::Punct(a), TokenTree::Punct(b)] if a.as_char() == ':' && b.as_char() == ':'
+    ) && !matches!(result.len().checked_sub(3).map(|x| &result[x]), Some(TokenTree::Ident(_) | TokenTree::Group(_)));
+
+    match is_leading_colon || (is_path_start(result) && path.segments.len() > 1 && self.is_external_root(first)) {
+      true => len,
+      false => 0,
+    }
+  }
+
+  /// `tokio_tungstenite::connect_async`, `::std::fs::read`
+  pub fn is_external_path(&self, path: &Path) -> bool {
+    path.leading_colon.is_some()
+      || (path.segments.len() > 1 && self.is_external_root(&path.segments[0].ident))
+  }
+
+  /// The first segment is not `crate`, `self`, `super`, `Self` or a name defined in the template
+  fn is_external_root(&self, ident: &Ident) -> bool {
+    !matches!(ident.to_string().as_str(), "crate" | "self" | "super" | "Self")
+      && !self.definitions.contains(ident)
+  }
+
+  /// Folds the `use` tree, imports of other crates are kept as is
+  pub fn fold_use_root(&mut self, tree: UseTree) -> UseTree {
+    match tree {
+      UseTree::Path(x) if self.is_external_root(&x.ident) => UseTree::Path(x),
+      UseTree::Group(mut x) => {
+        x.items = x.items.into_iter().map(|x| self.fold_use_root(x)).collect();
+        UseTree::Group(x)
+      },
+      tree => fold::fold_use_tree(self, tree),
+    }
+  }
+
+  /// Collects names defined in the template: items, fields, variants, methods and generic params
+  pub fn collect_definitions(&mut self, items: &[Item]) {
+    for item in items {
+      match item {
+        Item::Const(x) => { self.definitions.insert(x.ident.clone()); },
+        Item::Enum(x) => {
+          self.definitions.insert(x.ident.clone());
+          self.collect_generics(&x.generics);
+          for variant in x.variants.iter() {
+            self.definitions.insert(variant.ident.clone());
+            self.collect_fields(&variant.fields);
+          }
+        },
+        Item::Fn(x) => {
+          self.definitions.insert(x.sig.ident.clone());
+          self.collect_generics(&x.sig.generics);
+          self.collect_stmts(&x.block.stmts);
+        },
+        Item::Impl(x) => {
+          self.collect_generics(&x.generics);
+          for item in x.items.iter() {
+            match item {
+              ImplItem::Const(x) => { self.definitions.insert(x.ident.clone()); },
+              ImplItem::Fn(x) => {
+                self.definitions.insert(x.sig.ident.clone());
+                self.collect_generics(&x.sig.generics);
+                self.collect_stmts(&x.block.stmts);
+              },
+              ImplItem::Type(x) => { self.definitions.insert(x.ident.clone()); },
+              _ => { },
+            }
+          }
+        },
+        Item::Macro(x) => if let Some(ident) = &x.ident {
+          self.definitions.insert(ident.clone());
+        },
+        Item::Mod(x) => {
+          self.definitions.insert(x.ident.clone());
+          if let Some((_, items)) = &x.content {
+            self.collect_definitions(items);
+          }
+        },
+        Item::Static(x) => { self.definitions.insert(x.ident.clone()); },
+        Item::Struct(x) => {
+          self.definitions.insert(x.ident.clone());
+          self.collect_generics(&x.generics);
+          self.collect_fields(&x.fields);
+        },
+        Item::Trait(x) => {
+          self.definitions.insert(x.ident.clone());
+          self.collect_generics(&x.generics);
+          for item in x.items.iter() {
+            match item {
+              TraitItem::Const(x) => { self.definitions.insert(x.ident.clone()); },
+              TraitItem::Fn(x) => {
+                self.definitions.insert(x.sig.ident.clone());
+                self.collect_generics(&x.sig.generics);
+                if let Some(block) = &x.default {
+                  self.collect_stmts(&block.stmts);
+                }
+              },
+              TraitItem::Type(x) => { self.definitions.insert(x.ident.clone()); },
+              _ => { },
+            }
+          }
+        },
+        Item::TraitAlias(x) => { self.definitions.insert(x.ident.clone()); },
+        Item::Type(x) => {
+          self.definitions.insert(x.ident.clone());
+          self.collect_generics(&x.generics);
+        },
+        Item::Union(x) => {
+          self.definitions.insert(x.ident.clone());
+          self.collect_generics(&x.generics);
+          self.definitions.extend(x.fields.named.iter().filter_map(|x| x.ident.clone()));
+        },
+        _ => { },
+      }
+    }
+  }
+
+  fn collect_stmts(&mut self, stmts: &[Stmt]) {
+    let items: Vec<Item> = stmts
+      .iter()
+      .filter_map(|x| match x {
+        Stmt::Item(x) => Some(x.clone()),
+        _ => None,
+      })
+      .collect();
+
+    self.collect_definitions(&items);
+  }
+
+  fn collect_fields(&mut self, fields: &Fields) {
+    self.definitions.extend(fields.iter().filter_map(|x| x.ident.clone()));
+  }
+
+  fn collect_generics(&mut self, generics: &Generics) {
+    self.definitions.extend(generics.params.iter().filter_map(|x| match x {
+      GenericParam::Type(x) => Some(x.ident.clone()),
+      GenericParam::Const(x) => Some(x.ident.clone()),
+      GenericParam::Lifetime(_) => None,
+    }));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::{HashMap, HashSet};
+
+  use quote::{quote, ToTokens};
+  use syn::{fold::Fold, parse_quote, Ident, ItemMod};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn rename_ident() {
+    let fold = SyncAFold {
+      idents: HashMap::from([
+        (parse_quote!(AsyncClient), parse_quote!(Client)),
+      ]),
+      ident_patterns: vec![
+        ("*_async".into(), "*".into()),
+        ("Async*".into(), "Blocking*".into()),
+        ("get_*".into(), "*".into()),
+      ],
+      definitions: HashSet::from([
+        parse_quote!(AsyncClient),
+        parse_quote!(connect_async),
+        parse_quote!(_async),
+        parse_quote!(AsyncRow),
+        parse_quote!(get_type),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let rename = |ident: Ident| fold.rename_ident(&ident).map(|x| x.to_string());
+
+    assert_eq!(rename(parse_quote!(connect)), None);
+    assert_eq!(rename(parse_quote!(_async)), None);
+    assert_eq!(rename(parse_quote!(AsyncClient)), Some("Client".into()));
+    assert_eq!(rename(parse_quote!(connect_async)), Some("connect".into()));
+    assert_eq!(rename(parse_quote!(AsyncRow)), Some("BlockingRow".into()));
+    assert_eq!(rename(parse_quote!(get_type)), None);
+    assert_eq!(rename(parse_quote!(open_async)), None);
+    assert_eq!(
+      fold.rename_tokens(quote!("{}", connect_async(&[AsyncRow]))).to_string(),
+      quote!("{}", connect(&[BlockingRow])).to_token_stream().to_string()
+    );
+    assert_eq!(
+      fold.rename_tokens(quote!(tokio_tungstenite::connect_async(url), ::ws::connect_async, crate::connect_async)).to_string(),
+      quote!(tokio_tungstenite::connect_async(url), ::ws::connect_async, crate::connect).to_string()
+    );
+  }
+
+  #[test]
+  fn rename_members() {
+    let mut fold = SyncAFold {
+      is_async: false,
+      ident_patterns: vec![("*_async".into(), "*".into())],
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let expected: ItemMod = parse_quote!(
+      mod my_mod {
+        struct File;
+
+        impl File {
+          fn read(&self) { }
+        }
+
+        fn copy(file: &File, sock: &Socket) {
+          file.read();
+          sock.read();
+          sock.write_async();
+          sock.read_async();
+        }
+      }
+    );
+
+    // receivers have no types: a method of another crate with a template name is renamed too
+    assert_eq!(
+      fold.fold_item_mod(parse_quote!(
+        mod my_mod {
+          struct File;
+
+          impl File {
+            async fn read_async(&self) { }
+          }
+
+          async fn copy(file: &File, sock: &Socket) {
+            file.read_async().await;
+            sock.read_async().await;
+            sock.write_async().await;
+            #[synca::rename(read_async => read_async)]
+            sock.read_async().await;
+          }
+        }
+      )).to_token_stream().to_string(),
+      expected.to_token_stream().to_string()
+    );
+  }
+}
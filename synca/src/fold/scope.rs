@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use syn::{parse::ParseStream, Token};
+
+use crate::{fold::cfg::Attrs, rename::Rename, replace::Replace, CfgPredicate, SyncAAttribute, SyncAFold};
+
+/// Arguments of `#[synca::replace(..)]` and `#[synca::rename(..)]`
+///
+/// Rules may be limited to modules: `#[synca::replace(sync: A => B)]`.
+#[derive(Debug)]
+pub struct ScopedRules {
+  pub is_rename: bool,
+  pub cfg: Option<CfgPredicate>,
+  pub tokens: TokenStream,
+}
+
+impl ScopedRules {
+  pub fn parse_with(is_rename: bool, input: ParseStream) -> syn::Result<Self> {
+    let fork = input.fork();
+    let has_cfg = fork.parse::<CfgPredicate>().is_ok()
+      && fork.peek(Token![:])
+      && !fork.peek(Token![::]);
+
+    let cfg = match has_cfg {
+      true => {
+        let cfg = input.parse()?;
+        input.parse::<Token![:]>()?;
+        Some(cfg)
+      },
+      false => None,
+    };
+
+    Ok(Self { is_rename, cfg, tokens: input.parse()? })
+  }
+}
+
+/// Rules that are restored after the scope
+struct SavedRules {
+  keep_async: bool,
+  async_replace: bool,
+  types: HashMap<syn::Type, syn::Type>,
+  type_patterns: Vec<(syn::Type, syn::Type)>,
+  paths: HashMap<syn::Path, syn::Path>,
+  attributes: Vec<(syn::Attribute, Vec<syn::Attribute>)>,
+  idents: HashMap<syn::Ident, syn::Ident>,
+  ident_patterns: Vec<(String, String)>,
+}
+
+impl SyncAFold {
+  /// Folds the node with rules of its `#[synca::replace]`, `#[synca::rename]`, 
+  /// `#[synca::keep_async]` and `#[synca::no_replace]`
+  pub fn scoped<T: Attrs>(&mut self, node: T, fold: impl FnOnce(&mut Self, T) -> T) -> T {
+    let attrs: Vec<_> = node
+      .attrs()
+      .iter()
+      .filter_map(|x| match SyncAAttribute::try_from(x.clone()) {
+        // Rules without a selector are applied to sync modules, like `replace!` and `rename!`
+        Ok(SyncAAttribute::Scope(x)) if x.cfg.as_ref().map_or(!self.is_async, |x| x.matches(self)) => 
+          Some(SyncAAttribute::Scope(x)),
+        Ok(x @ (SyncAAttribute::KeepAsync | SyncAAttribute::NoReplace)) => Some(x),
+        // Errors are reported by `SyncAFoldAttributes`
+        _ => None,
+      })
+      .collect();
+    if attrs.is_empty() {
+      return fold(self, node);
+    }
+
+    let saved = SavedRules {
+      keep_async: self.keep_async,
+      async_replace: self.async_replace,
+      types: self.types.clone(),
+      type_patterns: self.type_patterns.clone(),
+      paths: self.paths.clone(),
+      attributes: self.attributes.clone(),
+      idents: self.idents.clone(),
+      ident_patterns: self.ident_patterns.clone(),
+    };
+
+    for attr in attrs {
+      match attr {
+        SyncAAttribute::Scope(x) => {
+          // Async modules get only the scoped rules, `replace!` is for sync modules
+          if !x.is_rename && self.is_async && !self.async_replace {
+            self.async_replace = true;
+            self.types.clear();
+            self.type_patterns.clear();
+            self.paths.clear();
+            self.attributes.clear();
+          }
+
+          let result = match x.is_rename {
+            true => Rename::new(self).apply_tokens(x.tokens),
+            false => Replace::new(self).apply_tokens(x.tokens),
+          };
+          if let Err(e) = result {
+            self.errors.push(e);
+          }
+        },
+        // `async`, `.await`, futures and async types are kept, the module mode is not changed
+        SyncAAttribute::KeepAsync | SyncAAttribute::NoReplace => {
+          self.keep_async |= matches!(attr, SyncAAttribute::KeepAsync);
+          self.types.clear();
+          self.type_patterns.clear();
+          self.paths.clear();
+        },
+        _ => { },
+      }
+    }
+
+    let result = fold(self, node);
+
+    self.keep_async = saved.keep_async;
+    self.async_replace = saved.async_replace;
+    self.types = saved.types;
+    self.type_patterns = saved.type_patterns;
+    self.paths = saved.paths;
+    self.attributes = saved.attributes;
+    self.idents = saved.idents;
+    self.ident_patterns = saved.ident_patterns;
+
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use quote::ToTokens;
+  use syn::{fold::Fold, parse_quote, ItemMod};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn keep_async() {
+    let mut fold = SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio::sync::Mutex), parse_quote!(std::sync::Mutex)),
+      ]),
+      ..SyncAFold::new("blocking", parse_quote!(feature = "blocking"))
+    };
+    let expected: ItemMod = parse_quote!(
+      mod my_mod {
+        async fn helper(m: &tokio::sync::Mutex<i32>) -> i32 {
+          let is_async = false;
+          let mode = "sync";
+          *m.lock().await
+        }
+
+        fn get(m: &tokio::sync::Mutex<i32>) -> i32 {
+          *m.lock()
+        }
+
+        fn run(m: &std::sync::Mutex<i32>) -> i32 {
+          *m.lock()
+        }
+      }
+    );
+
+    assert_eq!(
+      fold.fold_item_mod(parse_quote!(
+        mod my_mod {
+          #[synca::keep_async]
+          async fn helper(m: &tokio::sync::Mutex<i32>) -> i32 {
+            #[synca::cfg(sync)]
+            let is_async = synca::is_async!();
+            let mode = synca::select! { async => "async", _ => "sync" };
+            *m.lock().await
+          }
+
+          #[synca::no_replace]
+          async fn get(m: &tokio::sync::Mutex<i32>) -> i32 {
+            *m.lock().await
+          }
+
+          async fn run(m: &tokio::sync::Mutex<i32>) -> i32 {
+            *m.lock().await
+          }
+        }
+      )).to_token_stream().to_string(),
+      expected.to_token_stream().to_string()
+    );
+  }
+
+  #[test]
+  fn scoped() {
+    let fold = |is_async, module_name| SyncAFold {
+      is_async,
+      types: HashMap::from([
+        (parse_quote!(tokio::sync::Mutex), parse_quote!(std::sync::Mutex)),
+      ]),
+      ..SyncAFold::new(module_name, parse_quote!(feature = "sync"))
+    };
+    let item: ItemMod = parse_quote!(
+      mod my_mod {
+        struct A(tokio::sync::Mutex<i32>);
+
+        #[synca::replace(tokio::sync::Mutex => parking_lot::Mutex)]
+        #[synca::rename(blocking: get_async => get)]
+        impl A {
+          fn get_async(&self) -> tokio::sync::Mutex<i32> { }
+        }
+
+        fn b() -> tokio::sync::Mutex<i32> { }
+
+        #[synca::rename(open_async => open)]
+        fn open_async() { }
+
+        #[synca::replace(tokio: std::sync::Mutex => std::sync::RwLock)]
+        fn c(m: tokio::sync::Mutex<i32>) -> std::sync::Mutex<i32> { }
+      }
+    );
+    let expected_sync: ItemMod = parse_quote!(
+      mod my_mod {
+        struct A(std::sync::Mutex<i32>);
+
+        impl A {
+          fn get_async(&self) -> parking_lot::Mutex<i32> { }
+        }
+
+        fn b() -> std::sync::Mutex<i32> { }
+
+        fn open() { }
+
+        fn c(m: std::sync::Mutex<i32>) -> std::sync::Mutex<i32> { }
+      }
+    );
+    let expected_blocking: ItemMod = parse_quote!(
+      mod my_mod {
+        struct A(std::sync::Mutex<i32>);
+
+        impl A {
+          fn get(&self) -> parking_lot::Mutex<i32> { }
+        }
+
+        fn b() -> std::sync::Mutex<i32> { }
+
+        fn open() { }
+
+        fn c(m: std::sync::Mutex<i32>) -> std::sync::Mutex<i32> { }
+      }
+    );
+    let expected_async: ItemMod = parse_quote!(
+      mod my_mod {
+        struct A(tokio::sync::Mutex<i32>);
+
+        impl A {
+          fn get_async(&self) -> tokio::sync::Mutex<i32> { }
+        }
+
+        fn b() -> tokio::sync::Mutex<i32> { }
+
+        fn open_async() { }
+
+        fn c(m: tokio::sync::Mutex<i32>) -> std::sync::RwLock<i32> { }
+      }
+    );
+
+    assert_eq!(
+      fold(false, "sync").fold_item_mod(item.clone()).to_token_stream().to_string(),
+      expected_sync.to_token_stream().to_string()
+    );
+    assert_eq!(
+      fold(false, "blocking").fold_item_mod(item.clone()).to_token_stream().to_string(),
+      expected_blocking.to_token_stream().to_string()
+    );
+    assert_eq!(
+      fold(true, "tokio").fold_item_mod(item).to_token_stream().to_string(),
+      expected_async.to_token_stream().to_string()
+    );
+  }
+}
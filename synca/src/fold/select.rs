@@ -0,0 +1,162 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{parse::{Parse, ParseStream}, parse_quote, punctuated::Punctuated, Attribute, Block, Expr, Macro, Token};
+
+use crate::{CfgPredicate, SyncAAttribute, SyncAFold};
+
+/// Arguments of `synca::select! { tokio => expr, sync => { .. }, _ => expr }`
+struct Select(Vec<(Option<CfgPredicate>, TokenStream)>);
+
+impl Parse for Select {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut arms = vec![];
+
+    while !input.is_empty() {
+      let cfg = match input.parse::<Option<Token![_]>>()? {
+        Some(_) => None,
+        None => Some(input.parse()?),
+      };
+      input.parse::<Token![=>]>()?;
+
+      // Braces are removed: `sync => { use std::fs; }` selects the item
+      let body = match input.peek(syn::token::Brace) {
+        true => match input.parse::<TokenTree>()? {
+          TokenTree::Group(x) => x.stream(),
+          tt => tt.into_token_stream(),
+        },
+        false => input.parse::<Expr>()?.into_token_stream(),
+      };
+      arms.push((cfg, body));
+
+      if !input.is_empty() {
+        input.parse::<Token![,]>()?;
+      }
+    }
+
+    Ok(Self(arms))
+  }
+}
+
+impl Select {
+  /// Body of the first matching arm, nothing if no arm matches
+  fn body(self, fold: &SyncAFold) -> TokenStream {
+    self.0
+      .into_iter()
+      .find(|(cfg, _)| cfg.as_ref().is_none_or(|x| x.matches(fold)))
+      .map(|x| x.1)
+      .unwrap_or_default()
+  }
+}
+
+/// Argument of `#[synca::body(sync = { .. }, any(smol, async_std) = { .. })]`
+#[derive(Debug, PartialEq)]
+pub struct FnBody(pub CfgPredicate, pub Block);
+
+impl Parse for FnBody {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let cfg = input.parse()?;
+    input.parse::<Token![=]>()?;
+
+    Ok(Self(cfg, input.parse()?))
+  }
+}
+
+impl FnBody {
+  pub fn parse_list(input: ParseStream) -> syn::Result<Vec<Self>> {
+    Ok(Punctuated::<FnBody, Token![,]>::parse_terminated(input)?.into_iter().collect())
+  }
+}
+
+impl SyncAFold {
+  /// `synca::select! { sync => a, _ => b }` => `a` in sync modules
+  pub fn select_macro(&self, mac: &Macro) -> Option<syn::Result<TokenStream>> {
+    if mac.path != parse_quote!(synca::select) {
+      return None;
+    }
+
+    Some(syn::parse2::<Select>(mac.tokens.clone()).map(|x| x.body(self)))
+  }
+
+  /// Body of the fn from `#[synca::body(..)]` for this module
+  pub fn fn_body(&self, attrs: &[Attribute]) -> Option<Block> {
+    attrs
+      .iter()
+      .filter_map(|x| match SyncAAttribute::try_from(x.clone()) {
+        Ok(SyncAAttribute::Body(x)) => Some(x),
+        // Errors are reported by `SyncAFoldAttributes`
+        _ => None,
+      })
+      .flatten()
+      .find(|x| x.0.matches(self))
+      .map(|x| x.1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::ToTokens;
+  use syn::{fold::Fold, parse_quote, ItemMod};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn select() {
+    let fold = |is_async, module_name| SyncAFold {
+      is_async,
+      ..SyncAFold::new(module_name, parse_quote!(feature = "sync"))
+    };
+    let item: ItemMod = parse_quote!(
+      mod my_mod {
+        synca::select! {
+          tokio => { use tokio::fs; },
+          sync => { use std::fs; },
+        }
+
+        async fn read() -> String {
+          let name = synca::select! { async => "async".to_string(), _ => "sync".to_string() };
+          fs::read(name, synca::select! { tokio => Mode::Tokio, _ => Mode::Blocking }).await
+        }
+
+        #[synca::body(sync = { std::fs::read(path) })]
+        async fn read_path(path: &str) -> String {
+          tokio::fs::read(path).await
+        }
+      }
+    );
+    let expected_tokio: ItemMod = parse_quote!(
+      mod my_mod {
+        use tokio::fs;
+
+        async fn read() -> String {
+          let name = "async".to_string();
+          fs::read(name, Mode::Tokio).await
+        }
+
+        async fn read_path(path: &str) -> String {
+          tokio::fs::read(path).await
+        }
+      }
+    );
+    let expected_sync: ItemMod = parse_quote!(
+      mod my_mod {
+        use std::fs;
+
+        fn read() -> String {
+          let name = "sync".to_string();
+          fs::read(name, Mode::Blocking)
+        }
+
+        fn read_path(path: &str) -> String { std::fs::read(path) }
+      }
+    );
+
+    assert_eq!(
+      fold(true, "tokio").fold_item_mod(item.clone()).to_token_stream().to_string(),
+      expected_tokio.to_token_stream().to_string()
+    );
+    assert_eq!(
+      fold(false, "sync").fold_item_mod(item).to_token_stream().to_string(),
+      expected_sync.to_token_stream().to_string()
+    );
+  }
+}
@@ -0,0 +1,53 @@
+use proc_macro2::{Group, TokenStream, TokenTree};
+
+/// Removes `.await` from the token tree, literals and spans are kept as is
+///
+/// `assert_eq!(dao.answer().await, 42)` => `assert_eq!(dao.answer(), 42)`
+pub fn strip_await(tokens: TokenStream) -> TokenStream {
+  let mut result = vec![];
+  let mut iter = tokens.into_iter().peekable();
+
+  while let Some(tt) = iter.next() {
+    match tt {
+      TokenTree::Punct(p) if p.as_char() == '.' => match iter.peek() {
+        Some(TokenTree::Ident(i)) if i == "await" => { iter.next(); },
+        _ => result.push(TokenTree::Punct(p)),
+      },
+      TokenTree::Group(g) => {
+        let mut group = Group::new(g.delimiter(), strip_await(g.stream()));
+        group.set_span(g.span());
+        result.push(TokenTree::Group(group));
+      },
+      tt => result.push(tt),
+    }
+  }
+
+  result.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use proc_macro2::{Span, TokenStream, TokenTree};
+  use quote::{quote, quote_spanned};
+
+  use super::strip_await;
+
+  #[test]
+  fn strip() {
+    assert_eq!(
+      strip_await(quote!(dao.answer().await?, [x.await], "SELECT .await")).to_string(),
+      quote!(dao.answer()?, [x], "SELECT .await").to_string()
+    );
+    assert_eq!(strip_await(quote!(a..b, x.0)).to_string(), quote!(a..b, x.0).to_string());
+  }
+
+  #[test]
+  fn spans() {
+    let span = Span::mixed_site();
+    let tokens: TokenStream = quote_spanned!(span=> answer().await);
+    let stripped: Vec<TokenTree> = strip_await(tokens).into_iter().collect();
+
+    assert_eq!(stripped.len(), 2);
+    assert!(stripped.iter().all(|x| format!("{:?}", x.span()) == format!("{:?}", span)));
+  }
+}
@@ -0,0 +1,335 @@
+use syn::{punctuated::Punctuated, spanned::Spanned, Ident, Item, ItemUse, Path, PathSegment, Type, TypePath, UseTree};
+
+use crate::SyncAFold;
+
+impl SyncAFold {
+  /// Rewrites `use` trees according to `replace!`
+  ///
+  /// Imported names are kept (`use tokio::sync::Mutex` => `use std::sync::RwLock as Mutex`),
+  /// imports replaced with `_` are removed. If nothing is left, the tree is an empty group.
+  pub fn replace_use(&mut self, item: ItemUse) -> ItemUse {
+    let mut leaves = vec![];
+    use_leaves(&item.tree, vec![], &mut leaves);
+
+    let mut is_changed = false;
+    let mut new_leaves = vec![];
+    for leaf in leaves {
+      let path = leaf.path(item.leading_colon.is_some());
+      if self.is_removed(&path) {
+        is_changed = true;
+        continue;
+      }
+
+      let target = match &leaf.item {
+        UseItem::Glob => self.replace_prefix(&path, false, true),
+        _ => self.replace_path(&path, false).or_else(|| self.pattern_path(&path)),
+      };
+      let target = match target {
+        Some(x) if x.segments.iter().all(|s| s.arguments.is_empty()) => x,
+        _ => {
+          new_leaves.push(leaf);
+          continue;
+        }
+      };
+
+      match leaf.with_path(target) {
+        Ok(x) => {
+          is_changed = true;
+          new_leaves.push(x);
+        },
+        Err(e) => {
+          self.errors.push(e);
+          new_leaves.push(leaf);
+        },
+      }
+    }
+
+    if !is_changed {
+      return item;
+    }
+
+    let mut new_item = item;
+    new_item.tree = use_tree(new_leaves);
+    new_item
+  }
+
+  fn is_removed(&self, path: &Path) -> bool {
+    (1..=path.segments.len()).any(|len| {
+      let key = Path {
+        leading_colon: path.leading_colon,
+        segments: path.segments.iter().take(len).cloned().collect(),
+      };
+
+      matches!(
+        self.types.get(&Type::Path(TypePath { qself: None, path: key })),
+        Some(Type::Infer(_))
+      )
+    })
+  }
+}
+
+/// Names imported by the `use` item with their full paths (globs are skipped)
+pub fn use_imports(item: &ItemUse) -> Vec<(Ident, Path)> {
+  let mut leaves = vec![];
+  use_leaves(&item.tree, vec![], &mut leaves);
+
+  leaves
+    .iter()
+    .filter_map(|x| Some((x.local_name()?, x.path(item.leading_colon.is_some()))))
+    .filter(|(name, _)| name != "_")
+    .collect()
+}
+
+/// `use {};` - the result of removing every import of the tree
+pub fn is_empty_use(item: &Item) -> bool {
+  match item {
+    Item::Use(x) => matches!(&x.tree, UseTree::Group(g) if g.items.is_empty()),
+    _ => false,
+  }
+}
+
+#[derive(Clone, Debug)]
+struct UseLeaf {
+  path: Vec<Ident>,
+  item: UseItem,
+}
+
+#[derive(Clone, Debug)]
+enum UseItem {
+  Name(Ident),
+  Rename(Ident, Ident),
+  Glob,
+}
+
+impl UseLeaf {
+  /// Full path of the imported item (`self` is resolved to its parent)
+  fn path(&self, leading_colon: bool) -> Path {
+    let mut segments: Vec<Ident> = self.path.clone();
+    match &self.item {
+      UseItem::Name(x) | UseItem::Rename(x, _) if x != "self" => segments.push(x.clone()),
+      _ => { },
+    }
+
+    Path {
+      leading_colon: if leading_colon { Some(Default::default()) } else { None },
+      segments: segments.into_iter().map(PathSegment::from).collect(),
+    }
+  }
+
+  fn local_name(&self) -> Option<Ident> {
+    match &self.item {
+      UseItem::Name(x) if x == "self" => self.path.last().cloned(),
+      UseItem::Name(x) | UseItem::Rename(_, x) => Some(x.clone()),
+      UseItem::Glob => None,
+    }
+  }
+
+  fn with_path(&self, target: Path) -> syn::Result<UseLeaf> {
+    let span = target.span();
+    let mut path: Vec<Ident> = target.segments.into_iter().map(|x| x.ident).collect();
+    let item = match (&self.item, self.local_name()) {
+      (UseItem::Glob, _) => UseItem::Glob,
+      (UseItem::Name(x) | UseItem::Rename(x, _), Some(local)) if x == "self" => {
+        match path.last() == Some(&local) {
+          true => UseItem::Name(x.clone()),
+          false => UseItem::Rename(x.clone(), local),
+        }
+      },
+      (_, local) => {
+        let name = path.pop().ok_or_else(|| syn::Error::new(span, "SyncA can't import an empty path"))?;
+        match local {
+          Some(local) if local != name => UseItem::Rename(name, local),
+          _ => UseItem::Name(name),
+        }
+      },
+    };
+
+    Ok(UseLeaf { path, item })
+  }
+
+  fn is_self(&self) -> bool {
+    matches!(&self.item, UseItem::Name(x) | UseItem::Rename(x, _) if x == "self")
+  }
+}
+
+fn use_leaves(tree: &UseTree, prefix: Vec<Ident>, leaves: &mut Vec<UseLeaf>) {
+  match tree {
+    UseTree::Path(x) => {
+      let mut path = prefix;
+      path.push(x.ident.clone());
+      use_leaves(&x.tree, path, leaves);
+    },
+    UseTree::Name(x) => leaves.push(UseLeaf { path: prefix, item: UseItem::Name(x.ident.clone()) }),
+    UseTree::Rename(x) => leaves.push(
+      UseLeaf { path: prefix, item: UseItem::Rename(x.ident.clone(), x.rename.clone()) }
+    ),
+    UseTree::Glob(_) => leaves.push(UseLeaf { path: prefix, item: UseItem::Glob }),
+    UseTree::Group(x) =>
+      for item in x.items.iter() {
+        use_leaves(item, prefix.clone(), leaves);
+      },
+  }
+}
+
+fn use_tree(leaves: Vec<UseLeaf>) -> UseTree {
+  if leaves.len() == 1 {
+    // `fs::self` is not valid, `fs::{self}` is
+    if leaves[0].path.is_empty() && leaves[0].is_self() {
+      return UseTree::Group(syn::UseGroup {
+        brace_token: Default::default(),
+        items: Punctuated::from_iter([use_chain(&[], &leaves[0].item)]),
+      });
+    }
+
+    return use_chain(&leaves[0].path, &leaves[0].item);
+  }
+
+  let mut groups: Vec<(Option<Ident>, Vec<UseLeaf>)> = vec![];
+  for leaf in leaves {
+    let first = match leaf.path.first() {
+      Some(x) => x.clone(),
+      None => {
+        groups.push((None, vec![leaf]));
+        continue;
+      }
+    };
+
+    let rest = UseLeaf { path: leaf.path[1..].to_vec(), item: leaf.item };
+    match groups.iter_mut().find(|(k, _)| k.as_ref() == Some(&first)) {
+      Some((_, x)) => x.push(rest),
+      None => groups.push((Some(first), vec![rest])),
+    }
+  }
+
+  let mut items: Vec<UseTree> = groups
+    .into_iter()
+    .map(|(key, leaves)| match key {
+      Some(ident) => UseTree::Path(syn::UsePath {
+        ident,
+        colon2_token: Default::default(),
+        tree: Box::new(use_tree(leaves)),
+      }),
+      None => use_chain(&[], &leaves[0].item),
+    })
+    .collect();
+
+  if items.len() == 1 {
+    return items.remove(0);
+  }
+
+  UseTree::Group(syn::UseGroup {
+    brace_token: Default::default(),
+    items: items.into_iter().collect::<Punctuated<_, _>>(),
+  })
+}
+
+fn use_chain(path: &[Ident], item: &UseItem) -> UseTree {
+  // `std::fs::self` => `std::fs`, `std::fs::self as x` => `std::fs as x`
+  let (path, item) = match (path.split_last(), item) {
+    (Some((last, rest)), UseItem::Name(x)) if x == "self" => (rest, UseItem::Name(last.clone())),
+    (Some((last, rest)), UseItem::Rename(x, r)) if x == "self" => match last == r {
+      true => (rest, UseItem::Name(last.clone())),
+      false => (rest, UseItem::Rename(last.clone(), r.clone())),
+    },
+    _ => (path, item.clone()),
+  };
+
+  let mut tree = match &item {
+    UseItem::Name(x) => UseTree::Name(syn::UseName { ident: x.clone() }),
+    UseItem::Rename(x, r) => UseTree::Rename(syn::UseRename {
+      ident: x.clone(),
+      as_token: Default::default(),
+      rename: r.clone(),
+    }),
+    UseItem::Glob => UseTree::Glob(syn::UseGlob { star_token: Default::default() }),
+  };
+
+  for ident in path.iter().rev() {
+    tree = UseTree::Path(syn::UsePath {
+      ident: ident.clone(),
+      colon2_token: Default::default(),
+      tree: Box::new(tree),
+    });
+  }
+
+  tree
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use quote::ToTokens;
+  use syn::{parse_quote, ItemUse};
+
+  use crate::SyncAFold;
+
+  #[test]
+  fn replace_use() {
+    let mut fold = SyncAFold {
+      is_async: false,
+      types: HashMap::from([
+        (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
+        (parse_quote!(tokio::sync::Mutex), parse_quote!(std::sync::RwLock)),
+        (parse_quote!(tokio::fs), parse_quote!(std::fs)),
+        (parse_quote!(tokio::io::AsyncReadExt), parse_quote!(_)),
+        (parse_quote!(tokio::net::UdpSocket), parse_quote!(_)),
+      ]),
+      paths: HashMap::from([
+        (parse_quote!(tokio_postgres), parse_quote!(postgres)),
+      ]),
+      ..SyncAFold::new("sync", parse_quote!(feature = "sync"))
+    };
+    let mut replace = |item: ItemUse| fold.replace_use(item).to_token_stream().to_string();
+    let expected = |item: ItemUse| item.to_token_stream().to_string();
+
+    assert_eq!(
+      replace(parse_quote!(use std::collections::HashMap;)),
+      expected(parse_quote!(use std::collections::HashMap;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio_postgres::Client;)),
+      expected(parse_quote!(use postgres::Client;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::sync::Mutex;)),
+      expected(parse_quote!(use std::sync::RwLock as Mutex;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio_postgres::{Client as PgClient, Row, error::*};)),
+      expected(parse_quote!(use postgres::{Client as PgClient, Row, error::*};))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::fs::{self, File};)),
+      expected(parse_quote!(use std::fs::{self, File};))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::{fs::File, io::AsyncReadExt};)),
+      expected(parse_quote!(use std::fs::File;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio_postgres::*;)),
+      expected(parse_quote!(use postgres::*;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::io::AsyncReadExt;)),
+      expected(parse_quote!(use {};))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::fs::{self};)),
+      expected(parse_quote!(use std::fs;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::fs::{self as async_fs};)),
+      expected(parse_quote!(use std::fs as async_fs;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::{self, io::AsyncReadExt};)),
+      expected(parse_quote!(use tokio;))
+    );
+    assert_eq!(
+      replace(parse_quote!(use tokio::{net::{self, UdpSocket}, time};)),
+      expected(parse_quote!(use tokio::{net::{self}, time};))
+    );
+  }
+}
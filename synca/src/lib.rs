@@ -79,7 +79,13 @@
 //! }
 //! ```
 
+#![allow(clippy::test_attr_in_doctest)]
+
+mod errors;
+#[allow(clippy::module_inception)]
 mod fold;
+mod macros;
+mod rename;
 mod replace;
 mod synca;
 
@@ -88,6 +94,7 @@ pub(crate) use fold::*;
 use proc_macro::TokenStream;
 use syn::{fold::Fold, parse_macro_input};
 use quote::quote;
+use errors::Errors;
 use synca::SyncA;
 
 
@@ -96,10 +103,13 @@ use synca::SyncA;
 /// The macro creates copies of the module, as described.
 /// 
 /// The macro argument is a comma-separated description of the modules.
-/// Only modifier macros are allowed in the module content. There are two of them: 
+/// Only modifier macros are allowed in the module content. There are five of them: 
 /// 
 /// - sync!() - turns the module code into synchronous code, 
+/// - mode!() - adds `Mode` struct with `NAME` and `IS_ASYNC` constants of the module
 /// - replace!(my_async_type => my_sync_type) - allows you to replace types and attributes
+/// - rename!(my_async_fn => my_sync_fn) - allows you to rename identifiers
+/// - macros!(sqlx::query => verbatim) - sets how arguments of macros are processed
 /// 
 /// ## Example
 /// 
@@ -147,18 +157,46 @@ pub fn synca(attr: TokenStream, input: TokenStream) -> TokenStream {
   let item_mod: syn::ItemMod = parse_macro_input!(input);
 
   let mut modules = vec![];
+  let mut errors = Errors::default();
   for (_, module) in sa.modules.iter_mut() {
     let mut new_module = module.item_mod.clone();
     new_module.content = item_mod.content.clone();
 
-    modules.push(module.fold.fold_item_mod(new_module));
+    let mut new_module = module.fold.fold_item_mod(new_module);
+    if let (true, Some((_, items))) = (module.mode, &mut new_module.content) {
+      items.extend(module.fold.mode_items());
+    }
+
+    modules.push(new_module);
+    errors.extend(&module.fold.errors);
   }
 
-  quote! { #(#modules)* }.into()
+  let errors = errors.to_compile_error();
+  quote! { #(#modules)* #errors }.into()
 }
 
 
 #[proc_macro_attribute]
 pub fn ignore(_attr: TokenStream, input: TokenStream) -> TokenStream {
   input
-}
\ No newline at end of file
+}
+
+/// Code of the first arm matching the generated module, replaced by synca::synca
+#[proc_macro]
+pub fn select(_input: TokenStream) -> TokenStream {
+  quote! { 
+    compile_error!("synca::select! can be used only inside synca::synca, outside of #[synca::ignore] and verbatim macros")
+  }.into()
+}
+
+/// Name of the generated module as a string literal, replaced by synca::synca
+#[proc_macro]
+pub fn module_name(_input: TokenStream) -> TokenStream {
+  quote! { compile_error!("synca::module_name!() can be used only inside synca::synca") }.into()
+}
+
+/// `true` in async modules, `false` in sync modules, replaced by synca::synca
+#[proc_macro]
+pub fn is_async(_input: TokenStream) -> TokenStream {
+  quote! { compile_error!("synca::is_async!() can be used only inside synca::synca") }.into()
+}
@@ -0,0 +1,70 @@
+use syn::{parse::{ParseStream, Parser}, punctuated::Punctuated, Ident, Path, Token};
+
+use crate::SyncAFold;
+
+/// How arguments of the macro are processed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroPolicy {
+  /// Arguments are not changed
+  Verbatim,
+  /// Comma-separated expressions are folded
+  Exprs,
+  /// Items are folded
+  Items,
+  /// Identifiers are renamed and `.await` is removed
+  Tokens,
+  /// Local `macro_rules!` is expanded before folding
+  Expand,
+}
+
+impl syn::parse::Parse for MacroPolicy {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let ident: Ident = input.parse()?;
+    match ident.to_string().as_str() {
+      "verbatim" => Ok(MacroPolicy::Verbatim),
+      "exprs" => Ok(MacroPolicy::Exprs),
+      "items" => Ok(MacroPolicy::Items),
+      "tokens" => Ok(MacroPolicy::Tokens),
+      "expand" => Ok(MacroPolicy::Expand),
+      _ => Err(syn::Error::new(
+        ident.span(), 
+        "SyncA expected macro policy: verbatim, exprs, items, tokens or expand"
+      )),
+    }
+  }
+}
+
+pub struct Macros<'a> {
+  pub macros: &'a mut Vec<(Path, MacroPolicy)>,
+}
+
+struct MacrosItem(Path, MacroPolicy);
+
+impl syn::parse::Parse for MacrosItem {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let path = Path::parse_mod_style(input)?;
+    input.parse::<Token![=>]>()?;
+
+    Ok(MacrosItem(path, input.parse()?))
+  }
+}
+
+impl<'a> Macros<'a> {
+  pub fn new(fold: &'a mut SyncAFold) -> Self {
+    Self { macros: &mut fold.macros }
+  }
+
+  pub fn apply(&mut self, m: &syn::ItemMacro) -> syn::Result<()> {
+    let items = Punctuated::<MacrosItem, Token![,]>::parse_terminated.parse2(m.mac.tokens.clone())?;
+
+    // Rules keep the declaration order, a repeated rule replaces the policy
+    for item in items.into_iter() {
+      match self.macros.iter_mut().find(|(path, _)| *path == item.0) {
+        Some(x) => x.1 = item.1,
+        None => self.macros.push((item.0, item.1)),
+      }
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+
+use syn::{
+  ext::IdentExt,
+  parse::{ParseStream, Parser},
+  punctuated::Punctuated,
+  Ident, Token
+};
+
+use crate::SyncAFold;
+
+pub struct Rename<'a> {
+  pub idents: &'a mut HashMap<Ident, Ident>,
+  pub ident_patterns: &'a mut Vec<(String, String)>,
+}
+
+pub enum RenameItem {
+  Ident((Ident, Ident)),
+  Pattern((String, String)),
+}
+
+impl syn::parse::Parse for RenameItem {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let span = input.span();
+    let source = glob(input)?;
+    input.parse::<Token![=>]>()?;
+    let target = glob(input)?;
+
+    match (source, target) {
+      (Glob::Ident(source), Glob::Ident(target)) => Ok(RenameItem::Ident((source, target))),
+      (Glob::Pattern(source), Glob::Pattern(target)) => {
+        if source == "*" {
+          return Err(syn::Error::new(span, "SyncA expected rename pattern with a prefix or a suffix"));
+        }
+
+        Ok(RenameItem::Pattern((source, target)))
+      },
+      _ => Err(syn::Error::new(span, "SyncA expected rename!(name => new_name, *_async => *)")),
+    }
+  }
+}
+
+enum Glob {
+  Ident(Ident),
+  Pattern(String),
+}
+
+/// Parses `connect_async` or `*_async`
+fn glob(input: ParseStream) -> syn::Result<Glob> {
+  let prefix = match input.peek(Token![*]) {
+    true => None,
+    false => Some(Ident::parse_any(input)?),
+  };
+  if !input.peek(Token![*]) {
+    return match prefix {
+      Some(x) => Ok(Glob::Ident(x)),
+      None => Err(input.error("SyncA expected identifier")),
+    };
+  }
+
+  input.parse::<Token![*]>()?;
+  let suffix = match input.peek(Ident::peek_any) {
+    true => Some(Ident::parse_any(input)?),
+    false => None,
+  };
+
+  let to_string = |x: Option<Ident>| x.map(|x| x.to_string()).unwrap_or_default();
+  Ok(Glob::Pattern(format!("{}*{}", to_string(prefix), to_string(suffix))))
+}
+
+impl<'a> Rename<'a> {
+  pub fn new(fold: &'a mut SyncAFold) -> Self {
+    Self {
+      idents: &mut fold.idents,
+      ident_patterns: &mut fold.ident_patterns,
+    }
+  }
+
+  pub fn apply(&mut self, m: &syn::ItemMacro) -> syn::Result<()> {
+    self.apply_tokens(m.mac.tokens.clone())
+  }
+
+  /// Rules of `rename!(..)` or `#[synca::rename(..)]`
+  pub fn apply_tokens(&mut self, tokens: TokenStream) -> syn::Result<()> {
+    let items = Punctuated::<RenameItem, Token![,]>::parse_terminated.parse2(tokens)?;
+
+    for item in items.into_iter() {
+      match item {
+        RenameItem::Ident(x) => { self.idents.insert(x.0, x.1); },
+        RenameItem::Pattern(x) => self.ident_patterns.push(x),
+      }
+    }
+
+    Ok(())
+  }
+}
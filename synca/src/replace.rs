@@ -1,73 +1,191 @@
 use std::collections::HashMap;
 
+use proc_macro2::{Group, TokenStream, TokenTree};
 use quote::ToTokens;
-use syn::{parse::ParseStream, punctuated::Punctuated, parse_quote, Attribute, Token, Type};
+use syn::{
+  ext::IdentExt, 
+  parse::{ParseStream, Parser}, 
+  punctuated::Punctuated, 
+  Attribute, Ident, Path, PathSegment, Token, Type
+};
 
-use crate::SyncAFold;
+use crate::{fold::args_placeholder, SyncAFold};
+
+/// Placeholders (`$T`) are parsed as idents with this prefix
+const PLACEHOLDER_PREFIX: &str = "__synca_";
 
 pub struct Replace<'a> {
   pub types: &'a mut HashMap<Type, Type>,
-  pub attributes: &'a mut HashMap<Attribute, Attribute>,
+  pub type_patterns: &'a mut Vec<(Type, Type)>,
+  pub paths: &'a mut HashMap<Path, Path>,
+  pub attributes: &'a mut Vec<(Attribute, Vec<Attribute>)>,
 }
 
 pub enum ReplaceItem {
   Type((Type, Type)),
-  Attribute((Attribute, Attribute)),
+  Path((Path, Path)),
+  Attribute((Attribute, Vec<Attribute>)),
 }
 
 impl syn::parse::Parse for ReplaceItem {
   fn parse(input: ParseStream) -> syn::Result<Self> {
-    if let Ok(source) = input.parse::<Type>() {
+    if input.fork().call(wildcard).is_ok() {
+      let source = wildcard(input)?;
+      input.parse::<Token![=>]>()?;
+      return Ok(ReplaceItem::Path((source, wildcard(input)?)))
+    }
+
+    if !input.peek(Token![#]) {
+      let source = input.parse::<Type>()?;
       input.parse::<Token![=>]>()?;
       return Ok(ReplaceItem::Type((source, input.parse()?)))
     }
 
-    if let Ok(attrs) = Attribute::parse_outer(input) {
-      if attrs.len() != 1 {
-        return Err(
-          syn::Error::new(
-            input.span(), 
-            "SuncA expected one attribute line #[tokio::test] => #[test]"
-          )
-        );
-      }
+    let source = single_attribute(input)?;
+    input.parse::<Token![=>]>()?;
 
-      let source = attrs[0].clone();
-      input.parse::<Token![=>]>()?;
+    // `#[async_trait::async_trait] =>` removes the attribute
+    Ok(ReplaceItem::Attribute((source, Attribute::parse_outer(input)?)))
+  }
+}
 
-      let new_attrs = Attribute::parse_outer(input)?;
-      if new_attrs.len() != 1 {
-        return Err(
-          syn::Error::new(
-            input.span(), 
-            "SuncA expected one attribute line #[tokio::test] => #[test]"
-          )
-        );
-      }
+/// Parses `tokio_postgres::*` into `tokio_postgres`
+fn wildcard(input: ParseStream) -> syn::Result<Path> {
+  let mut path = Path { leading_colon: input.parse()?, segments: Punctuated::new() };
+
+  loop {
+    path.segments.push(PathSegment::from(Ident::parse_any(input)?));
+    input.parse::<Token![::]>()?;
+
+    if input.peek(Token![*]) {
+      input.parse::<Token![*]>()?;
+      return Ok(path);
+    }
+  }
+}
+
+/// `$T` => `__synca_T`
+fn placeholders(tokens: TokenStream) -> TokenStream {
+  let mut result = vec![];
+  let mut iter = tokens.into_iter().peekable();
+
+  while let Some(tt) = iter.next() {
+    match tt {
+      TokenTree::Punct(p) if p.as_char() == '$' => match iter.peek() {
+        Some(TokenTree::Ident(i)) => {
+          let ident = Ident::new(&format!("{}{}", PLACEHOLDER_PREFIX, i), i.span());
+          iter.next();
+          result.push(TokenTree::Ident(ident));
+        },
+        _ => result.push(TokenTree::Punct(p)),
+      },
+      TokenTree::Group(g) => {
+        let mut group = Group::new(g.delimiter(), placeholders(g.stream()));
+        group.set_span(g.span());
+        result.push(TokenTree::Group(group));
+      },
+      tt => result.push(tt),
+    }
+  }
+
+  result.into_iter().collect()
+}
+
+pub fn is_placeholder(ident: &Ident) -> bool {
+  ident.to_string().starts_with(PLACEHOLDER_PREFIX)
+}
 
-      return Ok(ReplaceItem::Attribute((source, new_attrs[0].clone())))
+fn placeholder_idents(tokens: TokenStream, idents: &mut Vec<Ident>) {
+  for tt in tokens {
+    match tt {
+      TokenTree::Ident(i) if is_placeholder(&i) => idents.push(i),
+      TokenTree::Group(g) => placeholder_idents(g.stream(), idents),
+      _ => { },
     }
-    
-    Err(syn::Error::new(input.span(), "Unhandled replace item (supported types and attributes)"))
+  }
+}
+
+fn single_attribute(input: ParseStream) -> syn::Result<Attribute> {
+  let span = input.span();
+  let mut attrs = Attribute::parse_outer(input)?;
+  if attrs.len() != 1 {
+    return Err(
+      syn::Error::new(span, "SyncA expected one attribute line #[tokio::test] => #[test]")
+    );
+  }
+
+  Ok(attrs.remove(0))
+}
+
+/// Rules keep the declaration order, a repeated rule replaces the target
+fn insert_ordered<K: PartialEq, V>(rules: &mut Vec<(K, V)>, key: K, value: V) {
+  match rules.iter_mut().find(|x| x.0 == key) {
+    Some(x) => x.1 = value,
+    None => rules.push((key, value)),
   }
 }
 
 impl<'a> Replace<'a> {
   pub fn new(fold: &'a mut SyncAFold) -> Self {
-    Self { types: &mut fold.types, attributes: &mut fold.attributes }
+    Self { 
+      types: &mut fold.types, 
+      type_patterns: &mut fold.type_patterns, 
+      paths: &mut fold.paths, 
+      attributes: &mut fold.attributes 
+    }
   }
 
   pub fn apply(&mut self, m: &syn::ItemMacro) -> syn::Result<()> {
-    let ts = m.mac.tokens.clone().into_token_stream();
-    let items: Punctuated::<ReplaceItem, Token![,]> = parse_quote!(#ts);
+    self.apply_tokens(m.mac.tokens.clone())
+  }
+
+  /// Rules of `replace!(..)` or `#[synca::replace(..)]`
+  pub fn apply_tokens(&mut self, tokens: TokenStream) -> syn::Result<()> {
+    let tokens = placeholders(tokens);
+    let items = Punctuated::<ReplaceItem, Token![,]>::parse_terminated.parse2(tokens)?;
 
     for item in items.iter() {
       match item {
-        ReplaceItem::Type(x) => { self.types.insert(x.0.clone(), x.1.clone()); },
-        ReplaceItem::Attribute(x) => { self.attributes.insert(x.0.clone(), x.1.clone()); },
+        ReplaceItem::Type(x) => {
+          let mut source = vec![];
+          let mut target = vec![];
+          placeholder_idents(x.0.to_token_stream(), &mut source);
+          placeholder_idents(x.1.to_token_stream(), &mut target);
+
+          if let Some(unbound) = target.iter().find(|x| !source.contains(x)) {
+            return Err(syn::Error::new(unbound.span(), "Placeholder is not bound by the pattern"));
+          }
+
+          match source.is_empty() {
+            true => { self.types.insert(x.0.clone(), x.1.clone()); },
+            false => insert_ordered(self.type_patterns, x.0.clone(), x.1.clone()),
+          }
+        },
+        ReplaceItem::Path(x) => { self.paths.insert(x.0.clone(), x.1.clone()); },
+        ReplaceItem::Attribute(x) => {
+          let mut source = vec![];
+          let mut target = vec![];
+          placeholder_idents(x.0.to_token_stream(), &mut source);
+          for attr in x.1.iter() {
+            placeholder_idents(attr.to_token_stream(), &mut target);
+          }
+
+          if !source.is_empty() && args_placeholder(&x.0).is_none() {
+            return Err(syn::Error::new(
+              source[0].span(), 
+              "SyncA expected attribute pattern #[tokio::main($args)] => #[main($args)]"
+            ));
+          }
+
+          if let Some(unbound) = target.iter().find(|x| !source.contains(x)) {
+            return Err(syn::Error::new(unbound.span(), "Placeholder is not bound by the pattern"));
+          }
+
+          insert_ordered(self.attributes, x.0.clone(), x.1.clone());
+        },
       }
     }
 
     Ok(())
   }
-}
\ No newline at end of file
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use quote::ToTokens;
 use syn::{punctuated::Punctuated, Expr, Token};
 
-use crate::{replace::Replace, SyncAFold};
+use crate::{errors::combine, macros::Macros, rename::Rename, replace::Replace, SyncAFold};
 
 #[derive(Debug, PartialEq)]
 pub struct SyncA {
@@ -14,76 +14,121 @@ pub struct SyncA {
 pub struct SyncAModule {
   pub cfg: syn::Expr,
   pub item_mod: syn::ItemMod,
-  pub fold: SyncAFold
+  pub fold: SyncAFold,
+  /// `Mode` struct is injected by `mode!()`
+  pub mode: bool,
 }
 
 impl syn::parse::Parse for SyncA {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
     let mut synca = SyncA { modules: HashMap::new() };
+    let mut errors = None;
 
     let parsed = Punctuated::<syn::ItemMod, Token![,]>::parse_terminated(input)?;
     for mod_desc in parsed.iter() {
-      synca.modules.insert(mod_desc.ident.clone().to_string(), mod_desc.into());
+      let name = mod_desc.ident.to_string();
+      if synca.modules.contains_key(&name) {
+        combine(
+          &mut errors, 
+          syn::Error::new_spanned(&mod_desc.ident, format!("Module {} is already defined", name))
+        );
+        continue;
+      }
+
+      match SyncAModule::try_from(mod_desc) {
+        Ok(module) => { synca.modules.insert(name, module); },
+        Err(e) => combine(&mut errors, e),
+      }
     }
 
-    Ok(synca)
+    match errors {
+      Some(e) => Err(e),
+      None => Ok(synca),
+    }
   }
 }
 
-impl From<&syn::ItemMod> for SyncAModule {
-  fn from(value: &syn::ItemMod) -> Self {
+impl TryFrom<&syn::ItemMod> for SyncAModule {
+  type Error = syn::Error;
+
+  fn try_from(value: &syn::ItemMod) -> syn::Result<Self> {
     let mut item_mod = value.clone();
     if let Some(x) = &mut item_mod.content {
       x.1.clear();
     }
 
+    let mut errors = None;
     let mut expr_cfg: Option<Expr> = None;
     for attr in value.attrs.iter() {
       if attr.path().is_ident("cfg") {
-        expr_cfg = Some(attr.parse_args().unwrap())
+        match attr.parse_args() {
+          Ok(x) => expr_cfg = Some(x),
+          Err(e) => combine(&mut errors, e),
+        }
       }
     }
     let cfg = match expr_cfg {
       Some(x) => x,
-      None => panic!("Module {} without cfg attribute", item_mod.ident.to_string())
+      None => {
+        let e = syn::Error::new_spanned(
+          &value.ident, 
+          format!("Module {} without cfg attribute", value.ident)
+        );
+        combine(&mut errors, e);
+        syn::parse_quote!(all())
+      }
     };
     
-    let mut fold = SyncAFold {
-      module_name: item_mod.ident.to_token_stream().to_string(),
-      is_async: true,
-      types: HashMap::new(),
-      attributes: HashMap::new(),
-      cfg: cfg.clone()
-    };
+    let mut fold = SyncAFold::new(item_mod.ident.to_token_stream().to_string(), cfg.clone());
+    let mut mode = false;
+
+    for content in value.content.clone().map(|x| x.1).unwrap_or_default().iter() {
+      if let syn::Item::Macro(m) = content {
+        if m.mac.path.is_ident("sync") {
+          fold.is_async = false;
+          continue;
+        }
 
-    for content in value.content.clone().map(|x| x.1).unwrap_or(vec![]).iter() {
-      match content {
-        syn::Item::Macro(m) => {
-          let macro_path = m.mac.path.clone().to_token_stream().to_string();
+        if m.mac.path.is_ident("mode") {
+          mode = true;
+          continue;
+        }
 
-          if m.mac.path.is_ident("sync") {
-            fold.is_async = false;
-            continue;
+        if m.mac.path.is_ident("replace") {
+          if let Err(e) = Replace::new(&mut fold).apply(m) {
+            combine(&mut errors, e);
           }
+          continue;
+        }
 
-          if m.mac.path.is_ident("replace") {
-            Replace::new(&mut fold).apply(m).unwrap();
-            continue;
+        if m.mac.path.is_ident("rename") {
+          if let Err(e) = Rename::new(&mut fold).apply(m) {
+            combine(&mut errors, e);
           }
+          continue;
+        }
 
-          panic!(
-            "Unhandled module item {}: supported only sync! and replace! macro\n\n  More about it: https://synca.sgr-team.dev/usage/index.html \n\n", 
-            macro_path, 
-          );          
-        },
-        _ => panic!(
-          "Unhandled module item {}: supported only sync! and replace! macro\n\n  More about it: https://synca.sgr-team.dev/usage/index.html \n\n", 
-          content.clone().to_token_stream().to_string(), 
-        )
+        if m.mac.path.is_ident("macros") {
+          if let Err(e) = Macros::new(&mut fold).apply(m) {
+            combine(&mut errors, e);
+          }
+          continue;
+        }
       }
+
+      combine(
+        &mut errors,
+        syn::Error::new_spanned(
+          content,
+          "Unhandled module item: supported only sync!, mode!, replace!, rename! and macros! macro\n\n  More about it: https://synca.sgr-team.dev/usage/index.html"
+        )
+      );
     }
 
-    SyncAModule { cfg, item_mod, fold }
+    match errors {
+      Some(e) => Err(e),
+      None => Ok(SyncAModule { cfg, item_mod, fold, mode }),
+    }
   }
 }
 
@@ -93,7 +138,7 @@ mod tests {
 
   use syn::parse_quote;
 
-  use crate::SyncAFold;
+  use crate::{macros::MacroPolicy, SyncAFold};
   use super::{SyncA, SyncAModule};
 
   #[test]
@@ -104,11 +149,18 @@ mod tests {
       #[cfg(feature = "sync")]
       mod my_mod_sync { 
         sync!();
+        mode!();
         replace!(
           tokio_postgres::Client => postgres::Client,
           tokio_postgres::Error => postgres::Error,
+          tokio::io::* => std::io::*,
+          tokio::sync::Mutex<$T> => std::sync::Mutex<$T>,
           #[tokio::test] => #[test],
+          #[async_trait::async_trait] =>,
+          #[tokio::main($args)] => #[inline] #[custom::main($args)],
         );
+        rename!(AsyncClient => Client, *_async => *);
+        macros!(sqlx::query => verbatim, my_crate::define_api => items);
       }
     );
     assert_eq!(
@@ -120,13 +172,8 @@ mod tests {
             SyncAModule { 
               cfg: parse_quote!(feature = "tokio"),
               item_mod: parse_quote!(#[cfg(feature = "tokio")] mod my_mod_tokio { }), 
-              fold: SyncAFold { 
-                module_name: "my_mod_tokio".into(), 
-                is_async: true, 
-                types: HashMap::new(), 
-                attributes: HashMap::new(),
-                cfg: parse_quote!(feature = "tokio")
-              } 
+              fold: SyncAFold::new("my_mod_tokio", parse_quote!(feature = "tokio")),
+              mode: false,
             }
           ),
           (
@@ -135,21 +182,112 @@ mod tests {
               cfg: parse_quote!(feature = "sync"),
               item_mod: parse_quote!(#[cfg(feature = "sync")] mod my_mod_sync { }), 
               fold: SyncAFold { 
-                module_name: "my_mod_sync".into(), 
                 is_async: false, 
                 types: HashMap::from([
                   (parse_quote!(tokio_postgres::Client), parse_quote!(postgres::Client)),
                   (parse_quote!(tokio_postgres::Error), parse_quote!(postgres::Error)),
                 ]), 
-                attributes: HashMap::from([
-                  (parse_quote!(#[tokio::test]), parse_quote!(#[test])),
+                type_patterns: vec![
+                  (
+                    parse_quote!(tokio::sync::Mutex<__synca_T>), 
+                    parse_quote!(std::sync::Mutex<__synca_T>)
+                  ),
+                ],
+                paths: HashMap::from([
+                  (parse_quote!(tokio::io), parse_quote!(std::io)),
+                ]),
+                attributes: vec![
+                  (parse_quote!(#[tokio::test]), vec![parse_quote!(#[test])]),
+                  (parse_quote!(#[async_trait::async_trait]), vec![]),
+                  (
+                    parse_quote!(#[tokio::main(__synca_args)]), 
+                    vec![parse_quote!(#[inline]), parse_quote!(#[custom::main(__synca_args)])]
+                  ),
+                ],
+                idents: HashMap::from([
+                  (parse_quote!(AsyncClient), parse_quote!(Client)),
                 ]),
-                cfg: parse_quote!(feature = "sync")
-              } 
+                ident_patterns: vec![("*_async".into(), "*".into())],
+                macros: vec![
+                  (parse_quote!(sqlx::query), MacroPolicy::Verbatim),
+                  (parse_quote!(my_crate::define_api), MacroPolicy::Items),
+                ],
+                ..SyncAFold::new("my_mod_sync", parse_quote!(feature = "sync"))
+              },
+              mode: true,
             }
           )
         ])
       }
     )
   }
-}
\ No newline at end of file
+
+  #[test]
+  pub fn errors() {
+    let errors = |tokens: proc_macro2::TokenStream| {
+      syn::parse2::<SyncA>(tokens)
+        .unwrap_err()
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      errors(quote::quote!(mod my_mod { })),
+      vec!["Module my_mod without cfg attribute"]
+    );
+    assert_eq!(
+      errors(quote::quote!(
+        #[cfg(feature = "tokio")]
+        mod my_mod { },
+        #[cfg(feature = "sync")]
+        mod my_mod { sync!(); }
+      )),
+      vec!["Module my_mod is already defined"]
+    );
+    assert_eq!(
+      errors(quote::quote!(
+        #[cfg(feature = "sync")]
+        mod my_mod { 
+          fn unknown() { }
+          replace!(tokio_postgres::Client);
+        }
+      )).len(),
+      2
+    );
+    assert_eq!(
+      errors(quote::quote!(
+        #[cfg(feature = "sync")]
+        mod my_mod { replace!(tokio::sync::Mutex<$T> => std::sync::Mutex<$U>); }
+      )),
+      vec!["Placeholder is not bound by the pattern"]
+    );
+    assert_eq!(
+      errors(quote::quote!(
+        #[cfg(feature = "sync")]
+        mod my_mod { replace!(#[tokio::main(flavor = $F)] => #[main]); }
+      )),
+      vec!["SyncA expected attribute pattern #[tokio::main($args)] => #[main($args)]"]
+    );
+    assert_eq!(
+      errors(quote::quote!(
+        #[cfg(feature = "sync")]
+        mod my_mod { 
+          rename!(* => *_sync);
+          rename!(connect_async => *);
+        }
+      )),
+      vec![
+        "SyncA expected rename pattern with a prefix or a suffix",
+        "SyncA expected rename!(name => new_name, *_async => *)",
+      ]
+    );
+    assert_eq!(
+      errors(quote::quote!(
+        #[cfg(feature = "sync")]
+        mod my_mod { macros!(sqlx::query => text); }
+      )),
+      vec!["SyncA expected macro policy: verbatim, exprs, items, tokens or expand"]
+    );
+  }
+}